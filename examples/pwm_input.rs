@@ -0,0 +1,41 @@
+#![deny(warnings)]
+#![deny(unsafe_code)]
+#![no_main]
+#![no_std]
+
+extern crate cortex_m;
+extern crate cortex_m_rt as rt;
+extern crate panic_halt;
+extern crate stm32g0xx_hal as hal;
+
+use cortex_m_semihosting::hprintln;
+use hal::prelude::*;
+use hal::stm32;
+use rt::entry;
+
+// Connect PA8 (TIM1_CH1) to PA6 (TIM3_CH1)
+#[entry]
+fn main() -> ! {
+    let dp = stm32::Peripherals::take().expect("cannot take peripherals");
+
+    let mut rcc = dp.RCC.constrain();
+    let gpioa = dp.GPIOA.split(&mut rcc);
+
+    let pwm = dp.TIM1.pwm(10.kHz(), &mut rcc);
+    let mut pwm_ch1 = pwm.bind_pin(gpioa.pa8);
+    pwm_ch1.set_duty(pwm_ch1.get_max_duty() / 4);
+    pwm_ch1.enable();
+
+    let mut pwm_input = dp.TIM3.pwm_input(gpioa.pa6, 1.MHz(), &mut rcc);
+
+    loop {
+        if pwm_input.is_ready() {
+            hprintln!(
+                "Frequency: {} Hz, period: {} ns, pulse: {} ns",
+                pwm_input.frequency().raw(),
+                pwm_input.period().ticks(),
+                pwm_input.pulse_width().ticks()
+            );
+        }
+    }
+}
@@ -1,10 +1,13 @@
 //! # Analog to Digital converter
 use core::convert::Infallible;
-use core::ptr;
 
+use crate::dma::{self, Channel as DmaChannel};
+use crate::dmamux::DmaMuxIndex;
 use crate::gpio::*;
-use crate::rcc::{Enable, Rcc};
+use crate::rcc::{ADCSrc, Enable, Rcc, SMEnable};
+use crate::signature::{TsCal1, VrefCal, VDDA_CALIB};
 use crate::stm32::ADC;
+use crate::time::Hertz;
 
 pub trait Channel<ADC> {
     type ID;
@@ -114,10 +117,143 @@ pub enum InjTrigSource {
     TRG_7 = 0b111, // EXTI11
 }
 
+/// Sampling time register of a channel
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum SampleTimeSel {
+    /// Sampling time set with [`Adc::set_sample_time`]
+    Smp1,
+    /// Sampling time set with [`Adc::set_sample_time2`]
+    Smp2,
+}
+
+/// Set of ADC channels converted in one sequence, implemented for tuples of up to
+/// 8 channels
+pub trait ChannelSet<const N: usize> {
+    fn channels(&self) -> [u8; N];
+}
+
+macro_rules! channel_set {
+    ($($N:literal: ($($T:ident),+),)+) => {
+        $(
+            impl<$($T),+> ChannelSet<$N> for ($($T,)+)
+            where
+                $($T: Channel<Adc, ID = u8>,)+
+            {
+                fn channels(&self) -> [u8; $N] {
+                    [$($T::channel()),+]
+                }
+            }
+        )+
+    };
+}
+
+channel_set! {
+    1: (A),
+    2: (A, B),
+    3: (A, B, C),
+    4: (A, B, C, D),
+    5: (A, B, C, D, E),
+    6: (A, B, C, D, E, F),
+    7: (A, B, C, D, E, F, G),
+    8: (A, B, C, D, E, F, G, H),
+}
+
+/// Sequence of channels converted with one start, see [`Adc::read_sequence`]
+///
+/// By default the channels are selected in the CHSELR bitmap (CHSELRMOD=0) and
+/// converted in ascending channel order, the results are reordered to match the
+/// channel set. With [`Sequence::ordered`] the sequencer (CHSELRMOD=1) converts up
+/// to 8 channels in the given order, channel 15 and above cannot be used.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Sequence<const N: usize> {
+    channels: [u8; N],
+    /// Channel set index of each conversion in bitmap mode
+    order: [u8; N],
+    ordered: bool,
+    smp2: u32,
+}
+
+impl<const N: usize> Sequence<N> {
+    /// Creates a sequence of the channels, sampled with the SMP1 sampling time
+    pub fn new<SET: ChannelSet<N>>(set: &SET) -> Self {
+        let channels = set.channels();
+        let mut order = [0; N];
+        let mut conversion = 0;
+        for channel in 0..=18 {
+            if let Some(index) = channels.iter().position(|&c| c == channel) {
+                order[conversion] = index as u8;
+                conversion += 1;
+            }
+        }
+        Sequence {
+            channels,
+            order,
+            // Channels used twice can only be converted by the sequencer
+            ordered: conversion < N,
+            smp2: 0,
+        }
+    }
+
+    /// Converts the channels in the given order with the fully configurable
+    /// sequencer (CHSELRMOD=1). Sets with a channel used twice are always ordered.
+    pub fn ordered(mut self) -> Self {
+        self.ordered = true;
+        self
+    }
+
+    /// Selects the sampling time of the channel at `index` in the channel set.
+    /// Channels share the selection: it applies to every occurrence of the channel.
+    pub fn sample_time(mut self, index: usize, sel: SampleTimeSel) -> Self {
+        let bit = 1 << self.channels[index];
+        match sel {
+            SampleTimeSel::Smp1 => self.smp2 &= !bit,
+            SampleTimeSel::Smp2 => self.smp2 |= bit,
+        }
+        self
+    }
+
+    /// Returns the channel numbers in the order of the channel set
+    pub fn channels(&self) -> [u8; N] {
+        self.channels
+    }
+
+    /// Reorders the `N` results of one sequence, in conversion order, to the order
+    /// of the channel set
+    pub fn reorder(&self, conversions: &[u16]) -> [u16; N] {
+        let mut results = [0; N];
+        for (conversion, &val) in conversions[..N].iter().enumerate() {
+            results[self.result_index(conversion)] = val;
+        }
+        results
+    }
+
+    fn result_index(&self, conversion: usize) -> usize {
+        if self.ordered {
+            conversion
+        } else {
+            self.order[conversion] as usize
+        }
+    }
+}
+
+/// Analog watchdog
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum AnalogWatchdog {
+    /// Guards a single channel or all converted channels
+    Awd1,
+    /// Guards any set of channels
+    Awd2,
+    /// Guards any set of channels
+    Awd3,
+}
+
 /// Analog to Digital converter interface
 pub struct Adc {
     rb: ADC,
     sample_time: SampleTime,
+    sample_time2: SampleTime,
     align: Align,
     precision: Precision,
     vref_cache: Option<u16>,
@@ -137,6 +273,7 @@ impl Adc {
         Self {
             rb: adc,
             sample_time: SampleTime::T_2,
+            sample_time2: SampleTime::T_2,
             align: Align::Right,
             precision: Precision::B_12,
             vref_cache: None,
@@ -191,11 +328,50 @@ impl Adc {
         self.rb.calfact().write(|w| w.calfact().set(calfact.0));
     }
 
+    /// Powers the ADC off between conversions (AUTOFF), it is powered up again
+    /// when a conversion starts
+    ///
+    /// Do not call if an ADC reading is ongoing.
+    pub fn set_auto_off(&mut self, enable: bool) {
+        self.rb.cfgr1().modify(|_, w| w.autoff().bit(enable));
+    }
+
+    /// Delays the next conversion until the last result was read (WAIT), no
+    /// result is overwritten when the CPU or DMA is slow
+    ///
+    /// Do not call if an ADC reading is ongoing.
+    pub fn set_wait(&mut self, enable: bool) {
+        self.rb.cfgr1().modify(|_, w| w.wait().bit(enable));
+    }
+
+    /// Enables the ADC voltage regulator, wait for tADCVREG_SETUP (20us) before
+    /// converting. The regulator is enabled by [`Self::new()`].
+    pub fn enable_regulator(&mut self) {
+        self.rb.cr().modify(|_, w| w.advregen().set_bit());
+    }
+
+    /// Disables the ADC voltage regulator to save power, the ADC must be disabled.
+    /// The calibration is kept.
+    pub fn disable_regulator(&mut self) {
+        assert!(self.rb.cr().read().aden().bit_is_clear());
+        self.rb.cr().modify(|_, w| w.advregen().clear_bit());
+    }
+
+    /// Returns true if the ADC voltage regulator is enabled
+    pub fn is_regulator_enabled(&self) -> bool {
+        self.rb.cr().read().advregen().bit_is_set()
+    }
+
     /// Set the Adc sampling time
     pub fn set_sample_time(&mut self, t_samp: SampleTime) {
         self.sample_time = t_samp;
     }
 
+    /// Set the second Adc sampling time, used by sequence channels selecting SMP2
+    pub fn set_sample_time2(&mut self, t_samp: SampleTime) {
+        self.sample_time2 = t_samp;
+    }
+
     /// Set the Adc result alignment
     pub fn set_align(&mut self, align: Align) {
         self.align = align;
@@ -253,14 +429,11 @@ impl Adc {
             vref_val
         };
 
-        let vref_cal: u32 = unsafe {
-            // DS12766 3.13.2
-            ptr::read_volatile(0x1FFF_75AA as *const u16) as u32
-        };
+        let vref_cal = VrefCal::get().read() as u32;
 
         // RM0454 14.9 Calculating the actual VDDA voltage using the internal reference voltage
         // V_DDA = 3 V x VREFINT_CAL / VREFINT_DATA
-        let vref = (vref_cal * 3_000_u32 / vref_val as u32) as u16;
+        let vref = (vref_cal * VDDA_CALIB / vref_val as u32) as u16;
         self.vref_cache = Some(vref);
         Ok(vref)
     }
@@ -276,6 +449,14 @@ impl Adc {
         }
     }
 
+    /// Sets the cached VREF value to a known reference voltage in mV, e.g. the
+    /// output of the voltage reference buffer, instead of measuring it
+    ///
+    /// Replaced by the next `read_vref`, which measures the same voltage.
+    pub fn set_reference_voltage(&mut self, mv: u16) {
+        self.vref_cache = Some(mv);
+    }
+
     pub fn read_voltage<PIN: Channel<Adc, ID = u8>>(
         &mut self,
         pin: &mut PIN,
@@ -300,13 +481,7 @@ impl Adc {
                 .bit(self.align == Align::Left)
         });
 
-        self.rb
-            .smpr()
-            .modify(|_, w| w.smp1().set(self.sample_time as u8));
-
-        self.rb
-            .chselr0()
-            .modify(|_, w| unsafe { w.bits(1 << PIN::channel()) });
+        self.select_channel(PIN::channel());
 
         self.rb.isr().modify(|_, w| w.eos().clear_bit_by_one());
         self.rb.cr().modify(|_, w| w.adstart().set_bit());
@@ -323,38 +498,270 @@ impl Adc {
         Ok(val)
     }
 
+    /// Converts all channels of the sequence, the results are in the order of the
+    /// channel set
+    pub fn read_sequence<const N: usize>(
+        &mut self,
+        seq: &Sequence<N>,
+    ) -> nb::Result<[u16; N], Infallible> {
+        self.power_up();
+        self.configure_sequence(seq);
+
+        self.rb
+            .isr()
+            .modify(|_, w| w.eoc().clear_bit_by_one().eos().clear_bit_by_one());
+        self.rb.cr().modify(|_, w| w.adstart().set_bit());
+
+        let mut results = [0; N];
+        for conversion in 0..N {
+            while self.rb.isr().read().eoc().bit_is_clear() {}
+            let res = self.rb.dr().read().bits() as u16;
+            let val = if self.align == Align::Left && self.precision == Precision::B_6 {
+                res << 8
+            } else {
+                res
+            };
+            results[seq.result_index(conversion)] = val;
+        }
+        while self.rb.isr().read().eos().bit_is_clear() {}
+
+        self.power_down();
+        Ok(results)
+    }
+
+    /// Reads the temperature sensor in °C
     pub fn read_temperature(&mut self) -> nb::Result<i16, Infallible> {
+        self.read_temperature_centi().map(|t| (t / 100) as i16)
+    }
+
+    /// Reads the temperature sensor in hundredths of °C, using the factory
+    /// calibration at 30°C and the typical slope of 2.5 mV/°C
+    ///
+    /// The G0 datasheets only document TS_CAL1 (30°C), so the slope can't be
+    /// calibrated from a second point.
+    pub fn read_temperature_centi(&mut self) -> nb::Result<i32, Infallible> {
         let mut vtemp = VTemp::new();
-        let vtemp_voltage: u16 = if vtemp.enabled(self) {
-            self.read_voltage(&mut vtemp)?
+        let raw = if vtemp.enabled(self) {
+            self.read(&mut vtemp)?
         } else {
             vtemp.enable(self);
-            let vtemp_voltage = self.read_voltage(&mut vtemp)?;
+            let raw = self.read(&mut vtemp)?;
             vtemp.disable(self);
-            vtemp_voltage
+            raw
         };
 
-        let ts_cal1: u32 = unsafe {
-            // DS12991 3.14.1
-            // at 3000 mV Vref+ and 30 degC
-            ptr::read_volatile(0x1FFF_75A8 as *const u16) as u32
+        // Scale the reading to the calibration supply voltage
+        let vdda = self.get_vref_cached()? as i32;
+        let ts = raw as i32 * vdda / VDDA_CALIB as i32;
+        let ts_cal1 = TsCal1::get().read() as i32;
+
+        // 100 / 2.5 mV/°C over a 4096 count range of 3.0 V
+        Ok(TsCal1::TEMP * 100 + (ts - ts_cal1) * VDDA_CALIB as i32 * 40 / 4096)
+    }
+
+    /// Returns the ADC reference voltage (VREF+) in mV, measured from the internal
+    /// reference like [`Self::read_vref`]
+    ///
+    /// This is the supply voltage (VDDA) only if VREF+ is tied to VDDA, not while
+    /// VREF+ is driven by the voltage reference buffer or an external reference.
+    pub fn read_supply_voltage(&mut self) -> nb::Result<u16, Infallible> {
+        self.read_vref()
+    }
+
+    /// Enables the analog watchdog on the channels. AWD1 guards a single channel,
+    /// use [`Self::enable_watchdog_all`] to guard all converted channels.
+    ///
+    /// Panics if the set of an AWD1 has more than one channel. Do not call if an
+    /// ADC reading is ongoing.
+    pub fn enable_watchdog<SET: ChannelSet<N>, const N: usize>(
+        &mut self,
+        awd: AnalogWatchdog,
+        channels: &SET,
+    ) {
+        let channels = channels.channels();
+        match awd {
+            AnalogWatchdog::Awd1 => {
+                assert!(N == 1, "AWD1 guards a single channel");
+                self.rb.cfgr1().modify(|_, w| unsafe {
+                    w.awd1ch()
+                        .bits(channels[0])
+                        .awd1sgl()
+                        .set_bit()
+                        .awd1en()
+                        .set_bit()
+                });
+            }
+            AnalogWatchdog::Awd2 | AnalogWatchdog::Awd3 => {
+                let bitmap = channels.iter().fold(0, |bits, &c| bits | 1 << c);
+                self.set_watchdog_bitmap(awd, bitmap);
+            }
+        }
+    }
+
+    /// Enables the analog watchdog on all converted channels
+    ///
+    /// Do not call if an ADC reading is ongoing.
+    pub fn enable_watchdog_all(&mut self, awd: AnalogWatchdog) {
+        match awd {
+            AnalogWatchdog::Awd1 => {
+                self.rb
+                    .cfgr1()
+                    .modify(|_, w| w.awd1sgl().clear_bit().awd1en().set_bit());
+            }
+            _ => self.set_watchdog_bitmap(awd, 0x7_ffff),
+        }
+    }
+
+    /// Disables the analog watchdog
+    ///
+    /// Do not call if an ADC reading is ongoing.
+    pub fn disable_watchdog(&mut self, awd: AnalogWatchdog) {
+        match awd {
+            AnalogWatchdog::Awd1 => {
+                self.rb.cfgr1().modify(|_, w| w.awd1en().clear_bit());
+            }
+            _ => self.set_watchdog_bitmap(awd, 0),
+        }
+    }
+
+    /// Sets the thresholds in raw 12-bit counts, the watchdog flags conversions
+    /// below `low` or above `high`. Lower precisions compare their result
+    /// left-aligned to 12 bits.
+    pub fn set_watchdog_thresholds(&mut self, awd: AnalogWatchdog, low: u16, high: u16) {
+        assert!(low <= high && high <= 0xfff);
+        let bits = (high as u32) << 16 | low as u32;
+        match awd {
+            AnalogWatchdog::Awd1 => self.rb.awd1tr().write(|w| unsafe { w.bits(bits) }),
+            AnalogWatchdog::Awd2 => self.rb.awd2tr().write(|w| unsafe { w.bits(bits) }),
+            AnalogWatchdog::Awd3 => self.rb.awd3tr().write(|w| unsafe { w.bits(bits) }),
         };
+    }
 
-        let v30 = (3000_u32 * ts_cal1) >> 12; // mV
-                                              // 2.5 mV/degC
-        let t = 30 + (vtemp_voltage as i32 - v30 as i32) * 10 / 25;
+    /// Sets the thresholds in mV, converted with the cached VDDA, see `read_vref`
+    pub fn set_watchdog_thresholds_mv(
+        &mut self,
+        awd: AnalogWatchdog,
+        low_mv: u16,
+        high_mv: u16,
+    ) -> nb::Result<(), Infallible> {
+        let vref = self.get_vref_cached()? as u32;
+        let to_raw = |mv: u16| (((mv as u32) << 12) / vref).min(0xfff) as u16;
+        self.set_watchdog_thresholds(awd, to_raw(low_mv), to_raw(high_mv));
+        Ok(())
+    }
 
-        Ok(t as i16)
+    /// Starts listening for conversions out of the watchdog thresholds
+    pub fn listen_watchdog(&mut self, awd: AnalogWatchdog) {
+        self.rb.ier().modify(|_, w| match awd {
+            AnalogWatchdog::Awd1 => w.awd1ie().set_bit(),
+            AnalogWatchdog::Awd2 => w.awd2ie().set_bit(),
+            AnalogWatchdog::Awd3 => w.awd3ie().set_bit(),
+        });
+    }
+
+    /// Stops listening for watchdog events
+    pub fn unlisten_watchdog(&mut self, awd: AnalogWatchdog) {
+        self.rb.ier().modify(|_, w| match awd {
+            AnalogWatchdog::Awd1 => w.awd1ie().clear_bit(),
+            AnalogWatchdog::Awd2 => w.awd2ie().clear_bit(),
+            AnalogWatchdog::Awd3 => w.awd3ie().clear_bit(),
+        });
+    }
+
+    /// Returns true if a conversion was out of the watchdog thresholds
+    pub fn is_watchdog_pending(&self, awd: AnalogWatchdog) -> bool {
+        let isr = self.rb.isr().read();
+        match awd {
+            AnalogWatchdog::Awd1 => isr.awd1().bit_is_set(),
+            AnalogWatchdog::Awd2 => isr.awd2().bit_is_set(),
+            AnalogWatchdog::Awd3 => isr.awd3().bit_is_set(),
+        }
+    }
+
+    /// Clears the watchdog flag
+    pub fn unpend_watchdog(&mut self, awd: AnalogWatchdog) {
+        self.rb.isr().write(|w| match awd {
+            AnalogWatchdog::Awd1 => w.awd1().clear_bit_by_one(),
+            AnalogWatchdog::Awd2 => w.awd2().clear_bit_by_one(),
+            AnalogWatchdog::Awd3 => w.awd3().clear_bit_by_one(),
+        });
+    }
+
+    fn set_watchdog_bitmap(&mut self, awd: AnalogWatchdog, bitmap: u32) {
+        match awd {
+            AnalogWatchdog::Awd2 => self.rb.awd2cr().write(|w| unsafe { w.bits(bitmap) }),
+            _ => self.rb.awd3cr().write(|w| unsafe { w.bits(bitmap) }),
+        };
     }
 
     pub fn release(self) -> ADC {
         self.rb
     }
 
+    /// Selects a single channel sampled with SMP1, undoing the CHSELRMOD and SMPSEL
+    /// settings of a sequence. The ADC must be enabled and not converting.
+    fn select_channel(&mut self, channel: u8) {
+        self.rb.cfgr1().modify(|_, w| w.chselrmod().clear_bit());
+        self.rb.smpr().write(|w| {
+            w.smp1()
+                .set(self.sample_time as u8)
+                .smp2()
+                .set(self.sample_time2 as u8)
+        });
+
+        // The channel selection is applied once CCRDY is set
+        self.rb.isr().modify(|_, w| w.ccrdy().clear_bit_by_one());
+        self.rb.chselr0().write(|w| unsafe { w.bits(1 << channel) });
+        while self.rb.isr().read().ccrdy().bit_is_clear() {}
+    }
+
+    /// Selects the sequence channels, the ADC must be enabled and not converting
+    fn configure_sequence<const N: usize>(&mut self, seq: &Sequence<N>) {
+        if seq.ordered {
+            assert!(N <= 8 && seq.channels.iter().all(|&c| c < 15));
+        }
+        self.rb.cfgr1().modify(|_, w| unsafe {
+            w.res()
+                .bits(self.precision as u8)
+                .align()
+                .bit(self.align == Align::Left)
+                .scandir()
+                .clear_bit()
+                .chselrmod()
+                .bit(seq.ordered)
+        });
+
+        self.rb.smpr().write(|w| unsafe {
+            w.bits(seq.smp2 << 8)
+                .smp1()
+                .set(self.sample_time as u8)
+                .smp2()
+                .set(self.sample_time2 as u8)
+        });
+
+        // The channel selection is applied once CCRDY is set
+        self.rb.isr().modify(|_, w| w.ccrdy().clear_bit_by_one());
+        if seq.ordered {
+            let mut sq = 0xffff_ffff;
+            for (rank, &channel) in seq.channels.iter().enumerate() {
+                sq &= !(0xf << (4 * rank));
+                sq |= (channel as u32) << (4 * rank);
+            }
+            self.rb.chselr1().write(|w| unsafe { w.bits(sq) });
+        } else {
+            let bitmap = seq.channels.iter().fold(0, |bits, &c| bits | 1 << c);
+            self.rb.chselr0().write(|w| unsafe { w.bits(bitmap) });
+        }
+        while self.rb.isr().read().ccrdy().bit_is_clear() {}
+    }
+
     fn power_up(&mut self) {
         self.rb.isr().modify(|_, w| w.adrdy().clear_bit_by_one());
         self.rb.cr().modify(|_, w| w.aden().set_bit());
-        while self.rb.isr().read().adrdy().bit_is_clear() {}
+        // In auto-off mode the ADC is only powered while converting
+        if self.rb.cfgr1().read().autoff().bit_is_clear() {
+            while self.rb.isr().read().adrdy().bit_is_clear() {}
+        }
     }
 
     fn power_down(&mut self) {
@@ -401,13 +808,7 @@ where
 
         self.power_up();
 
-        self.rb
-            .smpr() // set sampling time set 1 (ADSTART must be 0)
-            .modify(|_, w| w.smp1().set(self.sample_time as u8));
-
-        self.rb
-            .chselr0() // set active channel acording chapter 15.12.9 (ADC_CFGR1; CHSELRMOD=0)
-            .modify(|_, w| unsafe { w.bits(1 << PIN::channel()) });
+        self.select_channel(PIN::channel());
     }
 }
 
@@ -438,6 +839,219 @@ impl DmaMode<Adc> for Adc {
     }
 }
 
+/// One-shot conversions for low duty cycle sampling
+///
+/// The ADC runs from the HSI16 kernel clock in auto-off mode, so it also converts
+/// in low-power run, and its regulator is only enabled while sampling. Sample from
+/// the RTC wakeup or LPTIM interrupt waking the device from Stop mode:
+///
+/// ```ignore
+/// let mut sampler = LowPowerSampler::new(adc, &mut rcc);
+/// power.set_mode(PowerMode::LowPower(LowPowerMode::StopMode1));
+/// loop {
+///     cortex_m::asm::wfi(); // woken by the RTC wakeup timer
+///     let value = sampler.sample(&mut pin)?;
+/// }
+/// ```
+pub struct LowPowerSampler {
+    adc: Adc,
+    sys_clk: Hertz,
+}
+
+impl LowPowerSampler {
+    pub fn new(mut adc: Adc, rcc: &mut Rcc) -> Self {
+        // The clock mode and the regulator can only be changed with ADEN clear,
+        // e.g. after an injected conversion was prepared
+        if adc.rb.cr().read().aden().bit_is_set() {
+            adc.power_down();
+        }
+        rcc.set_adc_clock(ADCSrc::HSI);
+        ADC::sleep_mode_enable(rcc);
+        adc.set_clock_source(ClockSource::Async(AsyncClockDiv::AsyncD1));
+        adc.set_auto_off(true);
+        adc.disable_regulator();
+        LowPowerSampler {
+            adc,
+            sys_clk: rcc.clocks.sys_clk,
+        }
+    }
+
+    /// Enables the regulator, converts the channel and disables the regulator
+    pub fn sample<PIN: Channel<Adc, ID = u8>>(
+        &mut self,
+        pin: &mut PIN,
+    ) -> nb::Result<u16, Infallible> {
+        self.adc.enable_regulator();
+        // tADCVREG_SETUP
+        cortex_m::asm::delay(self.sys_clk.raw() / 50_000 + 1);
+        let value = self.adc.read(pin);
+        self.adc.disable_regulator();
+        value
+    }
+
+    /// Returns the ADC, e.g. to change the precision or the sampling time
+    pub fn adc(&mut self) -> &mut Adc {
+        &mut self.adc
+    }
+
+    pub fn release(mut self) -> Adc {
+        self.adc.set_auto_off(false);
+        self.adc.enable_regulator();
+        self.adc
+    }
+}
+
+/// Sequences converted on a timer trigger and streamed into a circular DMA buffer
+///
+/// The buffer holds an even number of sequences. While the DMA fills one half the
+/// other half can be read, see [`ContinuousAdc::readable_half`]. Each sequence is in
+/// conversion order, use [`Sequence::reorder`] to get the channel set order.
+/// Stopping and restarting begins again with the first conversion at the start
+/// of the buffer.
+pub struct ContinuousAdc<CH, const N: usize> {
+    adc: Adc,
+    channel: CH,
+    seq: Sequence<N>,
+    trigger: InjTrigSource,
+    buffer: &'static mut [u16],
+}
+
+impl<CH, const N: usize> ContinuousAdc<CH, N>
+where
+    CH: DmaChannel,
+{
+    /// Configures the ADC and the DMA channel and starts waiting for triggers
+    pub fn new(
+        adc: Adc,
+        mut channel: CH,
+        seq: Sequence<N>,
+        trigger: InjTrigSource,
+        buffer: &'static mut [u16],
+    ) -> Self {
+        assert!(!buffer.is_empty() && buffer.len() % (2 * N) == 0);
+        assert!(buffer.len() <= u16::MAX as usize);
+
+        channel.select_peripheral(DmaMuxIndex::ADC);
+        channel.set_direction(dma::Direction::FromPeripheral);
+        channel.set_word_size(dma::WordSize::BITS16);
+        channel.set_circular_mode(true);
+
+        let mut continuous = ContinuousAdc {
+            adc,
+            channel,
+            seq,
+            trigger,
+            buffer,
+        };
+        continuous.start();
+        continuous
+    }
+
+    /// Starts converting on triggers, from the start of the buffer
+    pub fn start(&mut self) {
+        let rb = &self.adc.rb;
+        self.channel
+            .set_peripheral_address(rb.dr().as_ptr() as u32, false);
+        self.channel
+            .set_memory_address(self.buffer.as_ptr() as u32, true);
+        self.channel.set_transfer_length(self.buffer.len() as u16);
+        self.channel.enable();
+
+        self.adc.power_up();
+        self.adc.configure_sequence(&self.seq);
+        self.adc.rb.cfgr1().modify(|_, w| unsafe {
+            w.exten()
+                .bits(1)
+                .extsel()
+                .bits(self.trigger as u8)
+                .dmaen()
+                .set_bit()
+                .dmacfg()
+                .set_bit()
+        });
+        self.adc.rb.isr().modify(|_, w| w.ovr().clear_bit_by_one());
+        self.adc.rb.cr().modify(|_, w| w.adstart().set_bit());
+    }
+
+    /// Stops at the end of the running conversion, and stops the DMA transfer
+    pub fn stop(&mut self) {
+        self.adc.rb.cr().modify(|_, w| w.adstp().set_bit());
+        while self.adc.rb.cr().read().adstart().bit_is_set() {}
+        self.channel.disable();
+        self.adc
+            .rb
+            .cfgr1()
+            .modify(|_, w| unsafe { w.exten().bits(0).dmaen().clear_bit().dmacfg().clear_bit() });
+        self.adc.power_down();
+    }
+
+    /// Replaces the sequence and the trigger, stops and restarts the conversions
+    pub fn reconfigure(&mut self, seq: Sequence<N>, trigger: InjTrigSource) {
+        self.stop();
+        self.seq = seq;
+        self.trigger = trigger;
+        self.start();
+    }
+
+    /// Returns the sequence converted
+    pub fn sequence(&self) -> &Sequence<N> {
+        &self.seq
+    }
+
+    /// Starts listening for half and full buffer completion
+    pub fn listen(&mut self) {
+        self.channel.listen(dma::Event::HalfTransfer);
+        self.channel.listen(dma::Event::TransferComplete);
+    }
+
+    /// Stops listening for half and full buffer completion
+    pub fn unlisten(&mut self) {
+        self.channel.unlisten(dma::Event::HalfTransfer);
+        self.channel.unlisten(dma::Event::TransferComplete);
+    }
+
+    /// Returns true if the first half of the buffer was filled
+    pub fn is_half_complete(&self) -> bool {
+        self.channel.event_occurred(dma::Event::HalfTransfer)
+    }
+
+    /// Returns true if the second half of the buffer was filled
+    pub fn is_complete(&self) -> bool {
+        self.channel.event_occurred(dma::Event::TransferComplete)
+    }
+
+    /// Returns the half of the buffer filled since the last call and clears its
+    /// flag, or `None` if no half was completed
+    pub fn readable_half(&mut self) -> Option<&[u16]> {
+        let half = self.buffer.len() / 2;
+        if self.channel.event_occurred(dma::Event::HalfTransfer) {
+            self.channel.clear_event(dma::Event::HalfTransfer);
+            Some(&self.buffer[..half])
+        } else if self.channel.event_occurred(dma::Event::TransferComplete) {
+            self.channel.clear_event(dma::Event::TransferComplete);
+            Some(&self.buffer[half..])
+        } else {
+            None
+        }
+    }
+
+    /// Returns true if a conversion result was lost because the DMA did not keep
+    /// up, and clears the flag
+    pub fn is_overrun(&mut self) -> bool {
+        let ovr = self.adc.rb.isr().read().ovr().bit_is_set();
+        if ovr {
+            self.adc.rb.isr().modify(|_, w| w.ovr().clear_bit_by_one());
+        }
+        ovr
+    }
+
+    /// Stops the conversions and releases the ADC, the DMA channel and the buffer
+    pub fn release(mut self) -> (Adc, CH, &'static mut [u16]) {
+        self.stop();
+        (self.adc, self.channel, self.buffer)
+    }
+}
+
 macro_rules! int_adc {
     ($($Chan:ident: ($chan:expr, $en:ident)),+ $(,)*) => {
         $(
@@ -6,8 +6,11 @@ use crate::analog::dac;
 use crate::exti::{Event as ExtiEvent, ExtiExt};
 use crate::gpio::*;
 use crate::rcc::{Clocks, Rcc};
+#[cfg(any(feature = "stm32g0b1", feature = "stm32g0c1"))]
+use crate::stm32::comp::COMP3_CSR;
 use crate::stm32::comp::{COMP1_CSR, COMP2_CSR};
-use crate::stm32::{COMP, EXTI};
+use crate::stm32::{COMP, EXTI, TIM1};
+use crate::timer::pins::{Break, Break2, BreakPin, OcrefClearSource};
 
 /// Enabled Comparator (type state)
 pub struct Enabled;
@@ -43,8 +46,21 @@ impl COMP2 {
     }
 }
 
+#[cfg(any(feature = "stm32g0b1", feature = "stm32g0c1"))]
+pub struct COMP3 {
+    _rb: PhantomData<()>,
+}
+
+#[cfg(any(feature = "stm32g0b1", feature = "stm32g0c1"))]
+impl COMP3 {
+    pub fn csr(&self) -> &COMP3_CSR {
+        // SAFETY: The COMP3 type is only constructed with logical ownership of
+        // these registers.
+        unsafe { &*COMP::ptr() }.comp3_csr()
+    }
+}
+
 // TODO: Split COMP in PAC
-// TODO: COMP3 for STM32G0Bxx etc.
 
 #[derive(Copy, Clone, Eq, PartialEq)]
 pub struct Config {
@@ -52,6 +68,7 @@ pub struct Config {
     hysteresis: Hysteresis,
     inverted: bool,
     output_xor: bool,
+    blanking: BlankingSource,
 }
 
 impl Default for Config {
@@ -61,6 +78,7 @@ impl Default for Config {
             inverted: false,
             power_mode: PowerMode::HighSpeed,
             output_xor: false,
+            blanking: BlankingSource::None,
         }
     }
 }
@@ -92,6 +110,13 @@ impl Config {
         self.output_xor = true;
         self
     }
+
+    /// Masks the output while the selected timer output compare signal is
+    /// active, e.g. to hide the current spike when a power switch turns on
+    pub fn blanking(mut self, blanking: BlankingSource) -> Self {
+        self.blanking = blanking;
+        self
+    }
 }
 
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
@@ -103,6 +128,18 @@ pub enum Hysteresis {
     High = 0b11,
 }
 
+/// Timer output compare signal blanking the comparator output
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum BlankingSource {
+    None = 0b00000,
+    Tim1Oc4 = 0b00001,
+    Tim1Oc5 = 0b00010,
+    Tim2Oc3 = 0b00100,
+    Tim3Oc3 = 0b01000,
+    Tim15Oc2 = 0b10000,
+}
+
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub enum PowerMode {
@@ -129,7 +166,7 @@ pub struct Open;
 #[derive(Copy, Clone, Eq, PartialEq)]
 pub struct Comp1InP;
 
-/// Comparator 2 positive input used as positive input for Comparator 1.
+/// Comparator 2 positive input used as positive input for Comparator 1 (or 3).
 /// Used to implement window comparator mode.
 #[derive(Copy, Clone, Eq, PartialEq)]
 pub struct Comp2InP;
@@ -146,6 +183,8 @@ macro_rules! window_input_pin {
 
 window_input_pin!(COMP1, Comp2InP);
 window_input_pin!(COMP2, Comp1InP);
+#[cfg(any(feature = "stm32g0b1", feature = "stm32g0c1"))]
+window_input_pin!(COMP3, Comp2InP);
 
 macro_rules! positive_input_pin {
     ($COMP:ident, $pin:ty, $bits:expr) => {
@@ -167,6 +206,15 @@ positive_input_pin!(COMP2, PB6<Analog>, 0b01);
 positive_input_pin!(COMP2, PA3<Analog>, 0b10);
 positive_input_pin!(COMP2, Open, 0b11);
 
+#[cfg(any(feature = "stm32g0b1", feature = "stm32g0c1"))]
+positive_input_pin!(COMP3, PE7<Analog>, 0b00);
+#[cfg(any(feature = "stm32g0b1", feature = "stm32g0c1"))]
+positive_input_pin!(COMP3, PE9<Analog>, 0b01);
+#[cfg(any(feature = "stm32g0b1", feature = "stm32g0c1"))]
+positive_input_pin!(COMP3, PC1<Analog>, 0b10);
+#[cfg(any(feature = "stm32g0b1", feature = "stm32g0c1"))]
+positive_input_pin!(COMP3, Open, 0b11);
+
 macro_rules! negative_input_pin {
     ($COMP:ident, $pin:ty, $bits:expr) => {
         impl NegativeInput<$COMP> for $pin {
@@ -185,6 +233,11 @@ negative_input_pin!(COMP2, PB3<Analog>, 0b0110);
 negative_input_pin!(COMP2, PB7<Analog>, 0b0111);
 negative_input_pin!(COMP2, PA2<Analog>, 0b1000);
 
+#[cfg(any(feature = "stm32g0b1", feature = "stm32g0c1"))]
+negative_input_pin!(COMP3, PE8<Analog>, 0b0110);
+#[cfg(any(feature = "stm32g0b1", feature = "stm32g0c1"))]
+negative_input_pin!(COMP3, PC0<Analog>, 0b0111);
+
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub enum RefintInput {
@@ -211,6 +264,8 @@ macro_rules! refint_input {
 
 refint_input!(COMP1);
 refint_input!(COMP2);
+#[cfg(any(feature = "stm32g0b1", feature = "stm32g0c1"))]
+refint_input!(COMP3);
 
 macro_rules! dac_input {
     ($COMP:ident, $channel:ty, $bits:expr) => {
@@ -222,16 +277,17 @@ macro_rules! dac_input {
     };
 }
 
-#[cfg(any(feature = "stm32g071", feature = "stm32g081"))]
 dac_input!(COMP1, dac::Channel1<ED>, 0b0100);
-#[cfg(any(feature = "stm32g071", feature = "stm32g081"))]
 dac_input!(COMP1, dac::Channel2<ED>, 0b0101);
 
-#[cfg(any(feature = "stm32g071", feature = "stm32g081"))]
 dac_input!(COMP2, dac::Channel1<ED>, 0b0100);
-#[cfg(any(feature = "stm32g071", feature = "stm32g081"))]
 dac_input!(COMP2, dac::Channel2<ED>, 0b0101);
 
+#[cfg(any(feature = "stm32g0b1", feature = "stm32g0c1"))]
+dac_input!(COMP3, dac::Channel1<ED>, 0b0100);
+#[cfg(any(feature = "stm32g0b1", feature = "stm32g0c1"))]
+dac_input!(COMP3, dac::Channel2<ED>, 0b0101);
+
 pub struct Comparator<C, ED> {
     regs: C,
     _enabled: PhantomData<ED>,
@@ -272,6 +328,8 @@ macro_rules! impl_comparator {
                         .bits(config.power_mode as u8)
                         .winout()
                         .bit(config.output_xor)
+                        .blanksel()
+                        .bits(config.blanking as u8)
                 });
 
                 Comparator {
@@ -352,6 +410,54 @@ macro_rules! impl_comparator {
 
 impl_comparator!(COMP1, comp1, ExtiEvent::COMP1);
 impl_comparator!(COMP2, comp2, ExtiEvent::COMP2);
+#[cfg(any(feature = "stm32g0b1", feature = "stm32g0c1"))]
+impl_comparator!(COMP3, comp3, ExtiEvent::COMP3);
+
+// Comparator outputs are internally connected to the TIM1 break inputs and
+// OCREF_CLR input, e.g. `pwm.enable_break(&comp, Polarity::ActiveHigh, 0)`
+macro_rules! impl_tim1_routing {
+    ($COMP:ident, $bkcmpe:ident, $bk2cmpe:ident, $ocref_clr:literal) => {
+        impl<ED> BreakPin<TIM1, Break> for &Comparator<$COMP, ED> {
+            fn setup(&self) {
+                let tim = unsafe { &*TIM1::ptr() };
+                tim.af1().modify(|_, w| w.$bkcmpe().set_bit());
+            }
+
+            fn release(self) -> Self {
+                let tim = unsafe { &*TIM1::ptr() };
+                tim.af1().modify(|_, w| w.$bkcmpe().clear_bit());
+                self
+            }
+        }
+
+        impl<ED> BreakPin<TIM1, Break2> for &Comparator<$COMP, ED> {
+            fn setup(&self) {
+                let tim = unsafe { &*TIM1::ptr() };
+                tim.af2().modify(|_, w| w.$bk2cmpe().set_bit());
+            }
+
+            fn release(self) -> Self {
+                let tim = unsafe { &*TIM1::ptr() };
+                tim.af2().modify(|_, w| w.$bk2cmpe().clear_bit());
+                self
+            }
+        }
+
+        impl<ED> OcrefClearSource<TIM1> for &Comparator<$COMP, ED> {
+            fn setup(&self) {
+                let tim = unsafe { &*TIM1::ptr() };
+                tim.or1()
+                    .modify(|_, w| unsafe { w.ocref_clr().bits($ocref_clr) });
+                tim.smcr().modify(|_, w| w.occs().clear_bit());
+            }
+        }
+    };
+}
+
+impl_tim1_routing!(COMP1, bkcmp1e, bk2cmp1e, 0b00);
+impl_tim1_routing!(COMP2, bkcmp2e, bk2cmp2e, 0b01);
+#[cfg(any(feature = "stm32g0b1", feature = "stm32g0c1"))]
+impl_tim1_routing!(COMP3, bkcmp3e, bk2cmp3e, 0b10);
 
 /// Uses two comparators to implement a window comparator.
 /// See Figure 69 in RM0444 Rev 5.
@@ -403,6 +509,12 @@ macro_rules! impl_window_comparator {
             }
         }
 
+        impl_window_comparator_methods!($UPPER, $LOWER);
+    };
+}
+
+macro_rules! impl_window_comparator_methods {
+    ($UPPER:ident, $LOWER:ident) => {
         impl WindowComparator<$UPPER, $LOWER, Disabled> {
             /// Enables the comparator
             pub fn enable(self) -> WindowComparator<$UPPER, $LOWER, Enabled> {
@@ -466,6 +578,8 @@ macro_rules! impl_window_comparator {
 
 impl_window_comparator!(COMP1, COMP2, Comp1InP);
 impl_window_comparator!(COMP2, COMP1, Comp2InP);
+#[cfg(any(feature = "stm32g0b1", feature = "stm32g0c1"))]
+impl_window_comparator_methods!(COMP3, COMP2);
 
 pub fn window_comparator12<
     I: PositiveInput<COMP1>,
@@ -479,7 +593,7 @@ pub fn window_comparator12<
     config: Config,
     rcc: &mut Rcc,
 ) -> WindowComparator<COMP1, COMP2, Disabled> {
-    let (comp1, comp2) = comp.split(rcc);
+    let (comp1, comp2, ..) = comp.split(rcc);
     (comp1, comp2).window_comparator(input, lower_threshold, upper_threshold, config, &rcc.clocks)
 }
 
@@ -495,31 +609,88 @@ pub fn window_comparator21<
     config: Config,
     rcc: &mut Rcc,
 ) -> WindowComparator<COMP2, COMP1, Disabled> {
-    let (comp1, comp2) = comp.split(rcc);
+    let (comp1, comp2, ..) = comp.split(rcc);
     (comp2, comp1).window_comparator(input, lower_threshold, upper_threshold, config, &rcc.clocks)
 }
 
-/// Enables the comparator peripheral, and splits the [`COMP`] into independent [`COMP1`] and [`COMP2`]
-pub fn split(_comp: COMP, rcc: &mut Rcc) -> (COMP1, COMP2) {
+/// Uses COMP3 and COMP2 to implement a window comparator. The input is connected to
+/// the COMP2 positive input and shared with COMP3, whose output is the window output.
+/// Ignores and overrides the `output_xor` setting in `config`.
+#[cfg(any(feature = "stm32g0b1", feature = "stm32g0c1"))]
+pub fn window_comparator32<
+    I: PositiveInput<COMP2>,
+    L: NegativeInput<COMP2>,
+    U: NegativeInput<COMP3>,
+>(
+    comp: COMP,
+    input: I,
+    lower_threshold: L,
+    upper_threshold: U,
+    config: Config,
+    rcc: &mut Rcc,
+) -> WindowComparator<COMP3, COMP2, Disabled> {
+    let (_, comp2, comp3) = comp.split(rcc);
+
+    let mut configu = config;
+    configu.output_xor = true;
+    let upper = comp3.comparator(Comp2InP, upper_threshold, configu, &rcc.clocks);
+
+    let mut configl = config;
+    configl.output_xor = false;
+    let lower = comp2.comparator(input, lower_threshold, configl, &rcc.clocks);
+
+    WindowComparator { upper, lower }
+}
+
+fn enable_comp(rcc: &mut Rcc) {
     // Enable COMP, SYSCFG, VREFBUF clocks
     rcc.rb.apbenr2().modify(|_, w| w.syscfgen().set_bit());
 
     // Reset COMP, SYSCFG, VREFBUF
     rcc.rb.apbrstr2().modify(|_, w| w.syscfgrst().set_bit());
     rcc.rb.apbrstr2().modify(|_, w| w.syscfgrst().clear_bit());
+}
 
+/// Enables the comparator peripheral, and splits the [`COMP`] into independent [`COMP1`] and [`COMP2`]
+#[cfg(any(feature = "stm32g071", feature = "stm32g081"))]
+pub fn split(_comp: COMP, rcc: &mut Rcc) -> (COMP1, COMP2) {
+    enable_comp(rcc);
     (COMP1 { _rb: PhantomData }, COMP2 { _rb: PhantomData })
 }
 
+/// Enables the comparator peripheral, and splits the [`COMP`] into independent [`COMP1`],
+/// [`COMP2`] and [`COMP3`]
+#[cfg(any(feature = "stm32g0b1", feature = "stm32g0c1"))]
+pub fn split(_comp: COMP, rcc: &mut Rcc) -> (COMP1, COMP2, COMP3) {
+    enable_comp(rcc);
+    (
+        COMP1 { _rb: PhantomData },
+        COMP2 { _rb: PhantomData },
+        COMP3 { _rb: PhantomData },
+    )
+}
+
 pub trait ComparatorSplit {
     /// Enables the comparator peripheral, and splits the [`COMP`] into independent [`COMP1`] and [`COMP2`]
+    #[cfg(any(feature = "stm32g071", feature = "stm32g081"))]
     fn split(self, rcc: &mut Rcc) -> (COMP1, COMP2);
+
+    /// Enables the comparator peripheral, and splits the [`COMP`] into independent [`COMP1`],
+    /// [`COMP2`] and [`COMP3`]
+    #[cfg(any(feature = "stm32g0b1", feature = "stm32g0c1"))]
+    fn split(self, rcc: &mut Rcc) -> (COMP1, COMP2, COMP3);
 }
 
 impl ComparatorSplit for COMP {
+    #[cfg(any(feature = "stm32g071", feature = "stm32g081"))]
     fn split(self, rcc: &mut Rcc) -> (COMP1, COMP2) {
         split(self, rcc)
     }
+
+    #[cfg(any(feature = "stm32g0b1", feature = "stm32g0c1"))]
+    fn split(self, rcc: &mut Rcc) -> (COMP1, COMP2, COMP3) {
+        split(self, rcc)
+    }
 }
 
 pub trait OutputPin<COMP> {
@@ -576,3 +747,12 @@ output_pin_push_pull!(COMP2, PB5<Output<PushPull>>);
 output_pin_open_drain!(COMP2, PB5<Output<OpenDrain>>);
 output_pin_push_pull!(COMP2, PB11<Output<PushPull>>);
 output_pin_open_drain!(COMP2, PB11<Output<OpenDrain>>);
+
+#[cfg(any(feature = "stm32g0b1", feature = "stm32g0c1"))]
+output_pin_push_pull!(COMP3, PC2<Output<PushPull>>);
+#[cfg(any(feature = "stm32g0b1", feature = "stm32g0c1"))]
+output_pin_open_drain!(COMP3, PC2<Output<OpenDrain>>);
+#[cfg(any(feature = "stm32g0b1", feature = "stm32g0c1"))]
+output_pin_push_pull!(COMP3, PE2<Output<PushPull>>);
+#[cfg(any(feature = "stm32g0b1", feature = "stm32g0c1"))]
+output_pin_open_drain!(COMP3, PE2<Output<OpenDrain>>);
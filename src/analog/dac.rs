@@ -3,6 +3,8 @@
 use core::marker::PhantomData;
 use core::mem::MaybeUninit;
 
+use crate::dma::{self, Channel as DmaChannel};
+use crate::dmamux::DmaMuxIndex;
 use crate::gpio::{DefaultMode, PA4, PA5};
 use crate::rcc::*;
 use crate::stm32::DAC;
@@ -34,12 +36,64 @@ impl GeneratorConfig {
     }
 }
 
+/// Conversion trigger, see TSELx in the reference manual
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Trigger {
+    /// Software trigger (SWTRIG), TSEL = 0
+    Software = 0,
+    /// TIM1 TRGO, TSEL = 1
+    Tim1Trgo = 1,
+    /// TIM2 TRGO, TSEL = 2
+    Tim2Trgo = 2,
+    /// TIM3 TRGO, TSEL = 3
+    Tim3Trgo = 3,
+    /// TIM6 TRGO, TSEL = 5
+    Tim6Trgo = 5,
+    /// TIM7 TRGO, TSEL = 6
+    Tim7Trgo = 6,
+    /// TIM15 TRGO, TSEL = 8
+    Tim15Trgo = 8,
+    /// LPTIM1 output, TSEL = 11
+    Lptim1Out = 11,
+    /// LPTIM2 output, TSEL = 12
+    Lptim2Out = 12,
+    /// EXTI line 9, TSEL = 13
+    Exti9 = 13,
+}
+
+/// Sample and hold timings, in LSI clock cycles
+///
+/// The output is sampled for `sample` cycles, then the buffer is off and the
+/// output capacitor holds the value for `hold` cycles, before a refresh of
+/// `refresh` cycles.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct SampleHoldConfig {
+    sample: u16,
+    hold: u16,
+    refresh: u8,
+}
+
+impl SampleHoldConfig {
+    pub fn new(sample: u16, hold: u16, refresh: u8) -> Self {
+        assert!(sample < 1024 && hold < 1024);
+        Self {
+            sample,
+            hold,
+            refresh,
+        }
+    }
+}
+
 /// Enabled DAC (type state)
 pub struct Enabled;
 /// Enabled DAC without output buffer (type state)
 pub struct EnabledUnbuffered;
 /// Enabled DAC wave generator (type state)
 pub struct WaveGenerator;
+/// Enabled DAC in sample and hold mode (type state)
+pub struct SampleAndHold;
 /// Disabled DAC (type state)
 pub struct Disabled;
 
@@ -47,8 +101,14 @@ pub trait ED {}
 impl ED for Enabled {}
 impl ED for EnabledUnbuffered {}
 impl ED for WaveGenerator {}
+impl ED for SampleAndHold {}
 impl ED for Disabled {}
 
+/// Enabled output states, which can be triggered and play DMA waveforms
+pub trait Output: ED {}
+impl Output for Enabled {}
+impl Output for EnabledUnbuffered {}
+
 pub struct Channel1<ED> {
     _enabled: PhantomData<ED>,
 }
@@ -99,7 +159,17 @@ macro_rules! dac {
         $wave:ident,
         $mamp:ident,
         $ten:ident,
-        $swtrig:ident
+        $swtrig:ident,
+        $tsel:ident,
+        $dmaen:ident,
+        $dmaudrie:ident,
+        $dmaudr:ident,
+        $dmamux:ident,
+        $shsr:ident,
+        $tsample:ident,
+        $thold:ident,
+        $trefresh:ident,
+        $bwst:ident
     ),)+) => {
         $(
             impl $CX<Disabled> {
@@ -125,6 +195,30 @@ macro_rules! dac {
                     }
                 }
 
+                /// Enables the channel in sample and hold mode, with the output buffer
+                /// only powered while sampling. The timings are clocked by the LSI,
+                /// which is enabled.
+                pub fn enable_sample_and_hold(
+                    self,
+                    config: SampleHoldConfig,
+                    rcc: &mut Rcc,
+                ) -> $CX<SampleAndHold> {
+                    let dac = unsafe { &(*DAC::ptr()) };
+                    rcc.enable_lsi();
+
+                    // The sample time register is busy while a write is applied
+                    while dac.sr().read().$bwst().bit_is_set() {}
+                    dac.$shsr().write(|w| unsafe { w.$tsample().bits(config.sample) });
+                    dac.shhr().modify(|_, w| unsafe { w.$thold().bits(config.hold) });
+                    dac.shrr().modify(|_, w| unsafe { w.$trefresh().bits(config.refresh) });
+                    dac.mcr().modify(|_, w| unsafe { w.$mode().bits(0b100) });
+                    dac.cr().modify(|_, w| w.$en().set_bit());
+
+                    $CX {
+                        _enabled: PhantomData,
+                    }
+                }
+
                 pub fn enable_generator(self, config: GeneratorConfig) -> $CX<WaveGenerator> {
                     let dac = unsafe { &(*DAC::ptr()) };
 
@@ -191,6 +285,103 @@ macro_rules! dac {
                 }
             }
 
+            impl<ED: Output> $CX<ED> {
+                /// Converts on the trigger instead of on each write to the data
+                /// holding register
+                pub fn set_trigger(&mut self, trigger: Trigger) {
+                    let dac = unsafe { &(*DAC::ptr()) };
+                    // The trigger selection can only be changed while disabled
+                    let en = dac.cr().read().$en().bit();
+                    dac.cr().modify(|_, w| w.$en().clear_bit());
+                    dac.cr().modify(|_, w| unsafe {
+                        w.$tsel().bits(trigger as u8).$ten().set_bit()
+                    });
+                    dac.cr().modify(|_, w| w.$en().bit(en));
+                }
+
+                /// Converts on each write to the data holding register
+                pub fn disable_trigger(&mut self) {
+                    let dac = unsafe { &(*DAC::ptr()) };
+                    let en = dac.cr().read().$en().bit();
+                    dac.cr().modify(|_, w| w.$en().clear_bit());
+                    dac.cr().modify(|_, w| w.$ten().clear_bit());
+                    dac.cr().modify(|_, w| w.$en().bit(en));
+                }
+
+                /// Outputs the samples once, one per trigger
+                pub fn play<CH: DmaChannel>(&mut self, channel: &mut CH, samples: &'static [u16]) {
+                    self.start_dma(channel, samples, false);
+                }
+
+                /// Outputs the samples repeatedly, one per trigger
+                pub fn play_circular<CH: DmaChannel>(
+                    &mut self,
+                    channel: &mut CH,
+                    samples: &'static [u16],
+                ) {
+                    self.start_dma(channel, samples, true);
+                }
+
+                /// Stops the DMA playback, the output keeps the last sample
+                pub fn stop<CH: DmaChannel>(&mut self, channel: &mut CH) {
+                    let dac = unsafe { &(*DAC::ptr()) };
+                    dac.cr().modify(|_, w| w.$dmaen().clear_bit());
+                    channel.disable();
+                }
+
+                /// Returns true if a trigger came before the DMA provided the sample.
+                /// The DMA requests then stop until `stop` and a new playback.
+                pub fn is_underrun(&self) -> bool {
+                    let dac = unsafe { &(*DAC::ptr()) };
+                    dac.sr().read().$dmaudr().bit_is_set()
+                }
+
+                /// Clears the underrun flag
+                pub fn clear_underrun(&mut self) {
+                    let dac = unsafe { &(*DAC::ptr()) };
+                    dac.sr().write(|w| w.$dmaudr().set_bit());
+                }
+
+                /// Starts listening for DMA underruns
+                pub fn listen_underrun(&mut self) {
+                    let dac = unsafe { &(*DAC::ptr()) };
+                    dac.cr().modify(|_, w| w.$dmaudrie().set_bit());
+                }
+
+                /// Stops listening for DMA underruns
+                pub fn unlisten_underrun(&mut self) {
+                    let dac = unsafe { &(*DAC::ptr()) };
+                    dac.cr().modify(|_, w| w.$dmaudrie().clear_bit());
+                }
+
+                fn start_dma<CH: DmaChannel>(
+                    &mut self,
+                    channel: &mut CH,
+                    samples: &'static [u16],
+                    circular: bool,
+                ) {
+                    let dac = unsafe { &(*DAC::ptr()) };
+                    assert!(
+                        dac.cr().read().$ten().bit_is_set(),
+                        "DMA playback requires a trigger"
+                    );
+                    assert!(!samples.is_empty() && samples.len() <= u16::MAX as usize);
+
+                    channel.disable();
+                    channel.select_peripheral(DmaMuxIndex::$dmamux);
+                    channel.set_direction(dma::Direction::FromMemory);
+                    channel.set_word_size(dma::WordSize::BITS16);
+                    channel.set_peripheral_address(dac.$dhrx().as_ptr() as u32, false);
+                    channel.set_memory_address(samples.as_ptr() as u32, true);
+                    channel.set_transfer_length(samples.len() as u16);
+                    channel.set_circular_mode(circular);
+                    channel.enable();
+
+                    dac.sr().write(|w| w.$dmaudr().set_bit());
+                    dac.cr().modify(|_, w| w.$dmaen().set_bit());
+                }
+            }
+
             /// DacOut implementation available in any Enabled/Disabled
             /// state
             impl<ED> DacOut<u16> for $CX<ED> {
@@ -216,6 +407,57 @@ macro_rules! dac {
     };
 }
 
+/// Both channels updated in the same cycle, from one write to the dual data
+/// holding registers
+pub struct DualChannel<ED1, ED2> {
+    ch1: Channel1<ED1>,
+    ch2: Channel2<ED2>,
+}
+
+impl<ED1, ED2> DualChannel<ED1, ED2> {
+    pub fn new(ch1: Channel1<ED1>, ch2: Channel2<ED2>) -> Self {
+        Self { ch1, ch2 }
+    }
+
+    /// Sets both 12-bit values right aligned (DHR12RD)
+    pub fn set_values(&mut self, val1: u16, val2: u16) {
+        let dac = unsafe { &(*DAC::ptr()) };
+        dac.dhr12rd()
+            .write(|w| unsafe { w.bits((val2 as u32 & 0xfff) << 16 | (val1 as u32 & 0xfff)) });
+    }
+
+    /// Sets both 8-bit values right aligned (DHR8RD)
+    pub fn set_values_8bit(&mut self, val1: u8, val2: u8) {
+        let dac = unsafe { &(*DAC::ptr()) };
+        dac.dhr8rd()
+            .write(|w| unsafe { w.bits((val2 as u32) << 8 | val1 as u32) });
+    }
+
+    /// Returns both output values
+    pub fn get_values(&mut self) -> (u16, u16) {
+        (self.ch1.get_value(), self.ch2.get_value())
+    }
+
+    pub fn release(self) -> (Channel1<ED1>, Channel2<ED2>) {
+        (self.ch1, self.ch2)
+    }
+}
+
+impl<ED1: Output, ED2: Output> DualChannel<ED1, ED2> {
+    /// Selects the same trigger for both channels
+    pub fn set_trigger(&mut self, trigger: Trigger) {
+        self.ch1.set_trigger(trigger);
+        self.ch2.set_trigger(trigger);
+    }
+
+    /// Triggers both channels at once, with `Trigger::Software` selected
+    pub fn trigger(&mut self) {
+        let dac = unsafe { &(*DAC::ptr()) };
+        dac.swtrgr()
+            .write(|w| w.swtrig1().set_bit().swtrig2().set_bit());
+    }
+}
+
 pub trait DacExt {
     fn constrain<PINS>(self, pins: PINS, rcc: &mut Rcc) -> PINS::Output
     where
@@ -245,7 +487,17 @@ dac!(
             wave1,
             mamp1,
             ten1,
-            swtrig1
+            swtrig1,
+            tsel1,
+            dmaen1,
+            dmaudrie1,
+            dmaudr1,
+            DAC_Channel1,
+            shsr1,
+            tsample1,
+            thold1,
+            trefresh1,
+            bwst1
         ),
     Channel2:
         (
@@ -260,6 +512,16 @@ dac!(
             wave2,
             mamp2,
             ten2,
-            swtrig2
+            swtrig2,
+            tsel2,
+            dmaen2,
+            dmaudrie2,
+            dmaudr2,
+            DAC_Channel2,
+            shsr2,
+            tsample2,
+            thold2,
+            trefresh2,
+            bwst2
         ),
 );
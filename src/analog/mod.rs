@@ -1,5 +1,22 @@
 pub mod adc;
-#[cfg(any(feature = "stm32g071", feature = "stm32g081"))]
+#[cfg(any(
+    feature = "stm32g071",
+    feature = "stm32g081",
+    feature = "stm32g0b1",
+    feature = "stm32g0c1"
+))]
 pub mod comparator;
-#[cfg(any(feature = "stm32g071", feature = "stm32g081"))]
+#[cfg(any(
+    feature = "stm32g071",
+    feature = "stm32g081",
+    feature = "stm32g0b1",
+    feature = "stm32g0c1"
+))]
 pub mod dac;
+#[cfg(any(
+    feature = "stm32g071",
+    feature = "stm32g081",
+    feature = "stm32g0b1",
+    feature = "stm32g0c1"
+))]
+pub mod vrefbuf;
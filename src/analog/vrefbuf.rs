@@ -0,0 +1,110 @@
+//! Voltage reference buffer (VREFBUF)
+//!
+//! Drives the VREF+ pin, the reference of the ADC and DAC, with a 2.048 V or 2.5 V
+//! internal reference. VDDA must be at least 2.4 V (2.048 V scale) or 2.8 V (2.5 V
+//! scale).
+//!
+//! Usage example:
+//! ```ignore
+//! let mut vrefbuf = dp.VREFBUF.constrain(&mut rcc);
+//! vrefbuf.enable(Scale::V2_048);
+//!
+//! // Convert ADC readings with the buffer voltage instead of measuring VDDA
+//! adc.set_reference_voltage(vrefbuf.millivolts().unwrap());
+//! let mv = adc.read_voltage(&mut pin).unwrap();
+//! ```
+use crate::rcc::{Enable, Rcc};
+use crate::stm32::{SYSCFG, VREFBUF};
+
+/// Output voltage of the buffer
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Scale {
+    /// 2.048 V
+    V2_048,
+    /// 2.5 V
+    V2_5,
+}
+
+impl Scale {
+    /// Returns the nominal output voltage in mV
+    pub fn millivolts(&self) -> u16 {
+        match self {
+            Scale::V2_048 => 2048,
+            Scale::V2_5 => 2500,
+        }
+    }
+}
+
+pub struct VrefBuf {
+    rb: VREFBUF,
+}
+
+pub trait VrefBufExt {
+    fn constrain(self, rcc: &mut Rcc) -> VrefBuf;
+}
+
+impl VrefBufExt for VREFBUF {
+    fn constrain(self, rcc: &mut Rcc) -> VrefBuf {
+        // VREFBUF is clocked with SYSCFG
+        SYSCFG::enable(rcc);
+        VrefBuf { rb: self }
+    }
+}
+
+impl VrefBuf {
+    /// Enables the buffer at `scale` and waits for the output voltage to settle
+    /// (VRR). VREF+ must not be driven externally.
+    pub fn enable(&mut self, scale: Scale) {
+        // The scale can only be changed with the buffer disabled
+        self.disable();
+        self.rb
+            .csr()
+            .modify(|_, w| w.vrs().bit(scale == Scale::V2_5).hiz().clear_bit());
+        self.rb.csr().modify(|_, w| w.envr().set_bit());
+        while self.rb.csr().read().vrr().bit_is_clear() {}
+    }
+
+    /// Disables the buffer, VREF+ is pulled down to VSSA unless in high-impedance
+    /// mode
+    pub fn disable(&mut self) {
+        self.rb.csr().modify(|_, w| w.envr().clear_bit());
+    }
+
+    /// Disconnects the buffer output from VREF+ (HIZ). With the buffer disabled
+    /// VREF+ can be supplied externally; with the buffer enabled (hold mode) the
+    /// voltage held on the VREF+ capacitor is used.
+    pub fn set_high_impedance(&mut self, enable: bool) {
+        self.rb.csr().modify(|_, w| w.hiz().bit(enable));
+    }
+
+    /// Returns true if the buffer is enabled and its output is ready (VRR)
+    pub fn is_ready(&self) -> bool {
+        let csr = self.rb.csr().read();
+        csr.envr().bit_is_set() && csr.vrr().bit_is_set()
+    }
+
+    /// Returns the selected scale
+    pub fn scale(&self) -> Scale {
+        if self.rb.csr().read().vrs().bit_is_set() {
+            Scale::V2_5
+        } else {
+            Scale::V2_048
+        }
+    }
+
+    /// Returns the voltage driven on VREF+ in mV, or `None` if the buffer isn't
+    /// driving it
+    pub fn millivolts(&self) -> Option<u16> {
+        let csr = self.rb.csr().read();
+        if csr.envr().bit_is_set() && csr.hiz().bit_is_clear() {
+            Some(self.scale().millivolts())
+        } else {
+            None
+        }
+    }
+
+    pub fn release(self) -> VREFBUF {
+        self.rb
+    }
+}
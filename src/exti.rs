@@ -22,11 +22,23 @@ pub enum Event {
     GPIO14 = 14,
     GPIO15 = 15,
     PVD = 16,
-    #[cfg(any(feature = "stm32g071", feature = "stm32g081"))]
+    #[cfg(any(
+        feature = "stm32g071",
+        feature = "stm32g081",
+        feature = "stm32g0b1",
+        feature = "stm32g0c1"
+    ))]
     COMP1 = 17,
-    #[cfg(any(feature = "stm32g071", feature = "stm32g081"))]
+    #[cfg(any(
+        feature = "stm32g071",
+        feature = "stm32g081",
+        feature = "stm32g0b1",
+        feature = "stm32g0c1"
+    ))]
     COMP2 = 18,
     RTC = 19,
+    #[cfg(any(feature = "stm32g0b1", feature = "stm32g0c1"))]
+    COMP3 = 20,
     TAMP = 21,
     I2C1 = 23,
     USART1 = 25,
@@ -153,7 +165,12 @@ impl ExtiExt for EXTI {
             }
         }
 
-        #[cfg(any(feature = "stm32g071", feature = "stm32g081"))]
+        #[cfg(any(
+            feature = "stm32g071",
+            feature = "stm32g081",
+            feature = "stm32g0b1",
+            feature = "stm32g0c1"
+        ))]
         match ev as u8 {
             line if line < 32 => {
                 let mask = !(1 << line);
@@ -1,6 +1,7 @@
+use crate::i2c::timing::{self, TimingError, TimingParams};
 use crate::i2c::SlaveAddressMask;
+use crate::rcc::I2CSrc;
 use crate::time::Hertz;
-use core::cmp;
 
 pub struct Config {
     pub speed: Option<Hertz>,
@@ -12,6 +13,10 @@ pub struct Config {
     pub address_11bits: bool,
     pub slave_address_2: u8,
     pub slave_address_mask: SlaveAddressMask,
+    pub clock_source: I2CSrc,
+    pub wakeup: bool,
+    pub rise_time: u32,
+    pub fall_time: u32,
 }
 
 impl Config {
@@ -26,6 +31,10 @@ impl Config {
             address_11bits: false,
             slave_address_2: 0,
             slave_address_mask: SlaveAddressMask::MaskNone,
+            clock_source: I2CSrc::PCLK,
+            wakeup: false,
+            rise_time: timing::DEFAULT_RISE_TIME,
+            fall_time: timing::DEFAULT_FALL_TIME,
         }
     }
 
@@ -40,6 +49,10 @@ impl Config {
             address_11bits: false,
             slave_address_2: 0,
             slave_address_mask: SlaveAddressMask::MaskNone,
+            clock_source: I2CSrc::PCLK,
+            wakeup: false,
+            rise_time: timing::DEFAULT_RISE_TIME,
+            fall_time: timing::DEFAULT_FALL_TIME,
         }
     }
 
@@ -59,27 +72,47 @@ impl Config {
         self
     }
 
-    pub fn timing_bits(&self, i2c_clk: Hertz) -> u32 {
+    /// Selects the I2C kernel clock. Apart from G0B1/G0C1, only I2C1 supports a
+    /// source other than PCLK.
+    pub fn clock_source(mut self, src: I2CSrc) -> Self {
+        self.clock_source = src;
+        self
+    }
+
+    /// Allows the slave to wake the MCU from Stop mode on own address match.
+    ///
+    /// Wake-up requires HSI16 as kernel clock and the digital filter disabled,
+    /// so both are configured here. Only supported by I2C1.
+    pub fn enable_wakeup(mut self) -> Self {
+        self.wakeup = true;
+        self.clock_source = I2CSrc::HSI;
+        self.digital_filter = 0;
+        self
+    }
+
+    /// Sets the SCL/SDA rise and fall times of the bus in ns, used for the
+    /// timing calculation
+    pub fn rise_fall_times(mut self, rise_time: u32, fall_time: u32) -> Self {
+        self.rise_time = rise_time;
+        self.fall_time = fall_time;
+        self
+    }
+
+    /// Returns the TIMINGR value for the kernel clock `i2c_clk`.
+    ///
+    /// An explicit timing given with [`Config::with_timing`] is returned as is.
+    pub fn timing_bits(&self, i2c_clk: Hertz) -> Result<u32, TimingError> {
         if let Some(bits) = self.timing {
-            return bits;
+            return Ok(bits);
         }
-        let speed = self.speed.unwrap();
-        let (psc, scll, sclh, sdadel, scldel) = if speed.raw() <= 100_000 {
-            let psc = 3;
-            let scll = cmp::min((((i2c_clk.raw() >> 1) / (psc + 1)) / speed.raw()) - 1, 255);
-            let sclh = scll - 4;
-            let sdadel = 2;
-            let scldel = 4;
-            (psc, scll, sclh, sdadel, scldel)
-        } else {
-            let psc = 1;
-            let scll = cmp::min((((i2c_clk.raw() >> 1) / (psc + 1)) / speed.raw()) - 1, 255);
-            let sclh = scll - 6;
-            let sdadel = 1;
-            let scldel = 3;
-            (psc, scll, sclh, sdadel, scldel)
+        let params = TimingParams {
+            speed: self.speed.unwrap(),
+            rise_time: self.rise_time,
+            fall_time: self.fall_time,
+            analog_filter: self.analog_filter,
+            digital_filter: self.digital_filter,
         };
-        psc << 28 | scldel << 20 | sdadel << 16 | sclh << 8 | scll
+        timing::calculate(i2c_clk, &params).map(|t| t.bits())
     }
 
     /// Slave address 1 as 7 bit address, in range 0 .. 127
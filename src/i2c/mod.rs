@@ -8,10 +8,18 @@ pub mod nonblocking;
 pub use nonblocking::*;
 
 pub mod config;
+pub mod register_slave;
+pub mod timing;
 
-use crate::rcc::{self, Rcc};
+use crate::exti::{Event as ExtiEvent, ExtiExt};
+#[cfg(not(feature = "stm32g0x0"))]
+use crate::power::{Power, PowerMode, StopMode};
+use crate::rcc::{self, I2CSrc, Rcc};
+use crate::stm32::{EXTI, SYSCFG};
+use crate::time::Hertz;
 pub use config::Config;
 use hal::i2c::{ErrorKind, NoAcknowledgeSource};
+pub use register_slave::I2cRegisterSlave;
 
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
@@ -87,10 +95,31 @@ pub trait Instance:
     + rcc::Enable
     + rcc::Reset
 {
+    /// Selects the kernel clock source and returns its frequency
+    fn kernel_clock(rcc: &mut Rcc, src: I2CSrc) -> Hertz;
 }
 
-impl Instance for crate::stm32::I2C1 {}
-impl Instance for crate::stm32::I2C2 {}
+impl Instance for crate::stm32::I2C1 {
+    fn kernel_clock(rcc: &mut Rcc, src: I2CSrc) -> Hertz {
+        rcc.set_i2c1_clock(src)
+    }
+}
+
+impl Instance for crate::stm32::I2C2 {
+    #[cfg(any(feature = "stm32g0b1", feature = "stm32g0c1"))]
+    fn kernel_clock(rcc: &mut Rcc, src: I2CSrc) -> Hertz {
+        rcc.set_i2c2_clock(src)
+    }
+
+    #[cfg(not(any(feature = "stm32g0b1", feature = "stm32g0c1")))]
+    fn kernel_clock(rcc: &mut Rcc, src: I2CSrc) -> Hertz {
+        assert!(
+            src == I2CSrc::PCLK,
+            "I2C2 is clocked by PCLK on this device"
+        );
+        rcc.clocks.apb_clk
+    }
+}
 
 /// I2C SDA pin
 pub trait SDAPin<I2C> {
@@ -141,6 +170,96 @@ pub struct I2c<I2C, SDA, SCL> {
     current_direction: I2cDirection,
 }
 
+/// Pins with a Fast-mode Plus 20 mA drive capability
+pub trait FastModePlusPin {
+    /// Enables or disables the Fm+ high drive on this pin
+    fn set_fast_mode_plus(&self, enable: bool, rcc: &mut Rcc);
+}
+
+fn syscfg(rcc: &mut Rcc) -> &'static crate::stm32::syscfg::RegisterBlock {
+    rcc.apbenr2().modify(|_, w| w.syscfgen().set_bit());
+    // NOTE(unsafe) only the I2C FM+ bits are touched via read-modify-write
+    unsafe { &*SYSCFG::ptr() }
+}
+
+macro_rules! fast_mode_plus_pins {
+    ($($PIN:ident: $fmp:ident,)+) => {
+        $(
+            impl<MODE> FastModePlusPin for crate::gpio::$PIN<MODE> {
+                fn set_fast_mode_plus(&self, enable: bool, rcc: &mut Rcc) {
+                    syscfg(rcc).cfgr1().modify(|_, w| w.$fmp().bit(enable));
+                }
+            }
+        )+
+    };
+}
+
+fast_mode_plus_pins! {
+    PA9: i2c_pa9_fmp,
+    PA10: i2c_pa10_fmp,
+    PB6: i2c_pb6_fmp,
+    PB7: i2c_pb7_fmp,
+    PB8: i2c_pb8_fmp,
+    PB9: i2c_pb9_fmp,
+}
+
+macro_rules! fast_mode_plus {
+    ($($I2CX:ident: $fmp:ident,)+) => {
+        $(
+            impl<SDA, SCL> I2c<crate::stm32::$I2CX, SDA, SCL> {
+                /// Enables or disables the Fm+ high drive on all I/Os used by this I2C,
+                /// required to run the bus at 1 MHz
+                pub fn set_fast_mode_plus(&mut self, enable: bool, rcc: &mut Rcc) {
+                    syscfg(rcc).cfgr1().modify(|_, w| w.$fmp().bit(enable));
+                }
+            }
+        )+
+    };
+}
+
+fast_mode_plus! {
+    I2C1: i2c1_fmp,
+    I2C2: i2c2_fmp,
+}
+
+impl<SDA, SCL> I2c<crate::stm32::I2C1, SDA, SCL> {
+    /// Arms wake-up from Stop mode on own address match.
+    ///
+    /// The peripheral must have been configured with [`Config::enable_wakeup`].
+    /// The address match interrupt is enabled, as it is the event that wakes the core.
+    pub fn listen_wakeup(&mut self, exti: &EXTI) {
+        self.i2c.cr1().modify(|_, w| {
+            w.wupen().set_bit();
+            w.addrie().set_bit()
+        });
+        exti.wakeup(ExtiEvent::I2C1);
+    }
+
+    /// Disarms wake-up from Stop mode
+    ///
+    /// Also disables the address match interrupt enabled by [`Self::listen_wakeup`],
+    /// listen for it again if the slave is interrupt driven.
+    pub fn unlisten_wakeup(&mut self, exti: &EXTI) {
+        self.i2c.cr1().modify(|_, w| {
+            w.wupen().clear_bit();
+            w.addrie().clear_bit()
+        });
+        exti.unlisten(ExtiEvent::I2C1);
+    }
+
+    /// Arms wake-up and configures `power` for the requested Stop mode.
+    ///
+    /// The core enters Stop on the next `wfi` with SLEEPDEEP set. When a master
+    /// addresses the slave, the I2C stretches SCL while the MCU wakes up, and the
+    /// transaction is resumed by the usual slave functions. The I2C is powered off
+    /// in Standby and Shutdown, which therefore can't be selected.
+    #[cfg(not(feature = "stm32g0x0"))]
+    pub fn set_stop_mode(&mut self, power: &mut Power, mode: StopMode, exti: &EXTI) {
+        self.listen_wakeup(exti);
+        power.set_mode(PowerMode::LowPower(mode.into()));
+    }
+}
+
 pub enum I2cPeripheralEvent {
     Read(u8),
     Write(u8),
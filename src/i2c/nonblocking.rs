@@ -157,6 +157,8 @@ where
         SCL: SCLPin<I2C>,
     {
         let config = config.into();
+        // Wake-up from Stop is only possible with the digital filter disabled
+        assert!(!config.wakeup || config.digital_filter == 0);
         I2C::enable(rcc);
         I2C::reset(rcc);
 
@@ -164,7 +166,10 @@ where
         i2c.cr1().modify(|_, w| w.pe().clear_bit());
 
         // Setup protocol timings
-        let timing_bits = config.timing_bits(rcc.clocks.apb_clk);
+        let i2c_clk = I2C::kernel_clock(rcc, config.clock_source);
+        let timing_bits = config
+            .timing_bits(i2c_clk)
+            .expect("I2C speed not reachable with this kernel clock");
         i2c.timingr().write(|w| unsafe { w.bits(timing_bits) });
 
         // Enable the I2C processing
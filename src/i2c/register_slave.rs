@@ -0,0 +1,330 @@
+//! Register-map I2C slave
+//!
+//! Emulates a register based device, like most I2C sensors: the first byte of a
+//! master write selects the register pointer, following bytes are written to the
+//! registers, and master reads return the registers starting at the pointer. The
+//! pointer auto-increments and wraps around at the end of the register bank.
+//!
+//! The whole protocol is handled from the I2C interrupt by calling
+//! [`I2cRegisterSlave::on_interrupt`].
+use super::{Error, I2c, Instance};
+use core::ops::Range;
+
+/// Master access rights of a register
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Access {
+    /// Register can be read and written by the master
+    ReadWrite,
+    /// Register can only be read, master writes are discarded
+    ReadOnly,
+    /// Register can be read and written, writes invoke the write callback
+    Notify,
+}
+
+/// Called from the interrupt after a master write to a [`Access::Notify`] register.
+///
+/// Receives the register bank, the register address and the written value. The
+/// value is already stored, the callback may overwrite it.
+pub type WriteCallback = fn(registers: &mut [u8], register: u8, value: u8);
+
+/// Register bank with the register pointer, independent of the I2C peripheral
+struct RegisterBank<const N: usize> {
+    registers: [u8; N],
+    access: [Access; N],
+    on_write: Option<WriteCallback>,
+    pointer: usize,
+    pointer_pending: bool,
+}
+
+impl<const N: usize> RegisterBank<N> {
+    fn new(registers: [u8; N]) -> Self {
+        assert!(N > 0 && N <= 256);
+        RegisterBank {
+            registers,
+            access: [Access::ReadWrite; N],
+            on_write: None,
+            pointer: 0,
+            pointer_pending: false,
+        }
+    }
+
+    /// Starts a master write, the first byte selects the register
+    fn start_write(&mut self) {
+        self.pointer_pending = true;
+    }
+
+    /// Handles a byte written by the master
+    fn write(&mut self, byte: u8) {
+        if self.pointer_pending {
+            self.pointer = byte as usize % N;
+            self.pointer_pending = false;
+            return;
+        }
+        let reg = self.pointer;
+        match self.access[reg] {
+            Access::ReadWrite => self.registers[reg] = byte,
+            Access::ReadOnly => {}
+            Access::Notify => {
+                self.registers[reg] = byte;
+                if let Some(callback) = self.on_write {
+                    callback(&mut self.registers, reg as u8, byte);
+                }
+            }
+        }
+        self.pointer = (reg + 1) % N;
+    }
+
+    /// Returns the next byte read by the master
+    fn read(&mut self) -> u8 {
+        let byte = self.registers[self.pointer];
+        self.pointer = (self.pointer + 1) % N;
+        byte
+    }
+
+    /// Moves the pointer back over a byte that was read but never sent
+    fn unread(&mut self) {
+        self.pointer = (self.pointer + N - 1) % N;
+    }
+
+    /// Ends the transfer
+    fn stop(&mut self) {
+        self.pointer_pending = false;
+    }
+}
+
+/// I2C slave emulating a bank of `N` 8-bit registers
+pub struct I2cRegisterSlave<I2C, SDA, SCL, const N: usize> {
+    i2c: I2c<I2C, SDA, SCL>,
+    bank: RegisterBank<N>,
+    errors: usize,
+}
+
+impl<I2C: Instance, SDA, SCL, const N: usize> I2cRegisterSlave<I2C, SDA, SCL, N> {
+    /// Takes a slave configured I2C and the initial register bank.
+    ///
+    /// The I2C must have an own address configured. Slave byte control is
+    /// disabled, so every byte written by the master is acknowledged.
+    pub fn new(i2c: I2c<I2C, SDA, SCL>, registers: [u8; N]) -> Self {
+        let bank = RegisterBank::new(registers);
+        i2c.i2c.cr1().modify(|_, w| w.sbc().clear_bit());
+        I2cRegisterSlave {
+            i2c,
+            bank,
+            errors: 0,
+        }
+    }
+
+    /// Sets the access rights of a range of registers
+    pub fn set_access(&mut self, registers: Range<usize>, access: Access) {
+        for a in &mut self.bank.access[registers] {
+            *a = access;
+        }
+    }
+
+    /// Sets the callback for writes to [`Access::Notify`] registers
+    pub fn set_write_callback(&mut self, callback: WriteCallback) {
+        self.bank.on_write = Some(callback);
+    }
+
+    /// Returns the register bank
+    pub fn registers(&self) -> &[u8; N] {
+        &self.bank.registers
+    }
+
+    /// Returns the register bank for updates by the application.
+    ///
+    /// Updates done while a master read is in progress may be seen partially.
+    pub fn registers_mut(&mut self) -> &mut [u8; N] {
+        &mut self.bank.registers
+    }
+
+    /// Returns the current register pointer
+    pub fn pointer(&self) -> u8 {
+        self.bank.pointer as u8
+    }
+
+    /// Get the global error counter. Reset to 0 after read
+    pub fn get_errors_reset(&mut self) -> usize {
+        let result = self.errors;
+        self.errors = 0;
+        result
+    }
+
+    /// Starts listening for the slave interrupt events
+    pub fn listen(&mut self) {
+        self.i2c.i2c.cr1().modify(|_, w| {
+            w.addrie().set_bit();
+            w.rxie().set_bit();
+            w.txie().set_bit();
+            w.nackie().set_bit();
+            w.stopie().set_bit();
+            w.errie().set_bit()
+        });
+    }
+
+    /// Stops listening for the slave interrupt events
+    pub fn unlisten(&mut self) {
+        self.i2c.i2c.cr1().modify(|_, w| {
+            w.addrie().clear_bit();
+            w.rxie().clear_bit();
+            w.txie().clear_bit();
+            w.nackie().clear_bit();
+            w.stopie().clear_bit();
+            w.errie().clear_bit()
+        });
+    }
+
+    /// Handles the pending I2C events, call this from the I2C interrupt
+    pub fn on_interrupt(&mut self) -> Result<(), Error> {
+        let i2c = &self.i2c.i2c;
+        let isr = i2c.isr().read();
+
+        if isr.berr().bit_is_set() {
+            i2c.icr().write(|w| w.berrcf().set_bit());
+            self.errors += 1;
+            return Err(Error::BusError);
+        }
+        if isr.arlo().bit_is_set() {
+            i2c.icr().write(|w| w.arlocf().set_bit());
+            self.errors += 1;
+            return Err(Error::ArbitrationLost);
+        }
+        if isr.ovr().bit_is_set() {
+            i2c.icr().write(|w| w.ovrcf().set_bit());
+            self.errors += 1;
+            return Err(Error::Overrun);
+        }
+
+        if isr.addr().bit_is_set() {
+            if isr.dir().bit_is_set() {
+                // Master read: flush TXDR so the first byte comes from the pointer
+                i2c.isr().write(|w| w.txe().set_bit());
+            } else {
+                // Master write: the first byte selects the register
+                self.bank.start_write();
+            }
+            // end address phase, release clock stretching
+            i2c.icr().write(|w| w.addrcf().set_bit());
+        }
+
+        if isr.rxne().bit_is_set() {
+            self.bank.write(i2c.rxdr().read().rxdata().bits());
+        }
+
+        if isr.txis().bit_is_set() {
+            let byte = self.bank.read();
+            i2c.txdr().write(|w| w.txdata().set(byte));
+        }
+
+        if isr.nackf().bit_is_set() {
+            // The master ends a read with a NACK
+            i2c.icr().write(|w| w.nackcf().set_bit());
+        }
+
+        if isr.stopf().bit_is_set() {
+            let isr = i2c.isr().read();
+            if isr.dir().bit_is_set() && isr.txe().bit_is_clear() {
+                // The byte preloaded into TXDR was never sent
+                self.bank.unread();
+            }
+            i2c.isr().write(|w| w.txe().set_bit());
+            i2c.icr().write(|w| w.stopcf().set_bit());
+            self.bank.stop();
+        }
+
+        Ok(())
+    }
+
+    /// Releases the I2C and the register bank
+    pub fn release(self) -> (I2c<I2C, SDA, SCL>, [u8; N]) {
+        self.i2c.i2c.cr1().modify(|_, w| w.sbc().set_bit());
+        (self.i2c, self.bank.registers)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bank() -> RegisterBank<4> {
+        RegisterBank::new([0x10, 0x11, 0x12, 0x13])
+    }
+
+    #[test]
+    fn pointer_write() {
+        let mut bank = bank();
+        bank.start_write();
+        bank.write(2);
+        assert_eq!(bank.pointer, 2);
+        assert_eq!(bank.registers, [0x10, 0x11, 0x12, 0x13]);
+
+        // Out of range pointers wrap around the bank
+        bank.start_write();
+        bank.write(7);
+        assert_eq!(bank.pointer, 3);
+    }
+
+    #[test]
+    fn write_auto_increment() {
+        let mut bank = bank();
+        bank.start_write();
+        bank.write(2);
+        bank.write(0xa2);
+        bank.write(0xa3);
+        bank.write(0xa0);
+        assert_eq!(bank.registers, [0xa0, 0x11, 0xa2, 0xa3]);
+        assert_eq!(bank.pointer, 1);
+    }
+
+    #[test]
+    fn read_auto_increment() {
+        let mut bank = bank();
+        bank.start_write();
+        bank.write(3);
+        bank.stop();
+        assert_eq!(bank.read(), 0x13);
+        assert_eq!(bank.read(), 0x10);
+        assert_eq!(bank.pointer, 1);
+    }
+
+    #[test]
+    fn unread() {
+        let mut bank = bank();
+        bank.read();
+        // The byte preloaded for the master NACK wasn't sent
+        bank.read();
+        bank.unread();
+        assert_eq!(bank.pointer, 1);
+
+        bank.pointer = 0;
+        bank.unread();
+        assert_eq!(bank.pointer, 3);
+    }
+
+    #[test]
+    fn stop_without_data() {
+        let mut bank = bank();
+        bank.start_write();
+        bank.stop();
+        // A new write after a STOP is data, not the pointer
+        bank.write(0xa0);
+        assert_eq!(bank.registers[0], 0xa0);
+    }
+
+    #[test]
+    fn access() {
+        fn double(registers: &mut [u8], register: u8, value: u8) {
+            registers[register as usize + 1] = value * 2;
+        }
+
+        let mut bank = bank();
+        bank.access[0] = Access::ReadOnly;
+        bank.access[1] = Access::Notify;
+        bank.on_write = Some(double);
+        bank.write(0xa0);
+        bank.write(0x21);
+        assert_eq!(bank.registers, [0x10, 0x21, 0x42, 0x13]);
+        assert_eq!(bank.pointer, 2);
+    }
+}
@@ -0,0 +1,413 @@
+//! I2C TIMINGR calculation
+//!
+//! Finds PRESC, SCLDEL, SDADEL, SCLH and SCLL for a kernel clock and bus speed,
+//! honouring the I2C-bus specification limits of the Standard, Fast and Fast-mode
+//! Plus modes, the rise/fall times of the bus and the analog/digital filter delays.
+//! The same approach as the STM32 reference tools is used: for every prescaler the
+//! first SCLDEL/SDADEL pair meeting tSU;DAT and tHD;DAT is selected, then for every
+//! SCL low period the high period closest to the requested frequency is derived.
+//!
+//! This module is plain integer logic without register access.
+use crate::time::Hertz;
+
+/// Analog filter minimum delay, in ns
+const ANALOG_FILTER_DELAY_MIN: u32 = 50;
+/// Analog filter maximum delay, in ns
+const ANALOG_FILTER_DELAY_MAX: u32 = 260;
+
+const PRESC_MAX: u32 = 16;
+const SCLDEL_MAX: u32 = 16;
+const SDADEL_MAX: u32 = 16;
+const SCLH_MAX: u32 = 256;
+const SCLL_MAX: u32 = 256;
+
+/// Default SCL/SDA rise time, in ns
+pub const DEFAULT_RISE_TIME: u32 = 25;
+/// Default SCL/SDA fall time, in ns
+pub const DEFAULT_FALL_TIME: u32 = 10;
+
+/// Timing calculation error
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum TimingError {
+    /// Requested speed is above Fast-mode Plus
+    SpeedTooHigh,
+    /// Rise or fall time exceeds the limit of the bus mode
+    RiseFallTime,
+    /// Digital filter longer than 15 cycles
+    DigitalFilter,
+    /// No register values meet the bus specification with this kernel clock
+    Unreachable,
+}
+
+/// I2C-bus specification limits of a bus mode, times in ns
+struct Spec {
+    rise_max: u32,
+    fall_max: u32,
+    hddat_min: u32,
+    vddat_max: u32,
+    sudat_min: u32,
+    l_min: u32,
+    h_min: u32,
+}
+
+const STANDARD: Spec = Spec {
+    rise_max: 1000,
+    fall_max: 300,
+    hddat_min: 0,
+    vddat_max: 3450,
+    sudat_min: 250,
+    l_min: 4700,
+    h_min: 4000,
+};
+
+const FAST: Spec = Spec {
+    rise_max: 300,
+    fall_max: 300,
+    hddat_min: 0,
+    vddat_max: 900,
+    sudat_min: 100,
+    l_min: 1300,
+    h_min: 600,
+};
+
+const FAST_PLUS: Spec = Spec {
+    rise_max: 120,
+    fall_max: 120,
+    hddat_min: 0,
+    vddat_max: 450,
+    sudat_min: 50,
+    l_min: 500,
+    h_min: 260,
+};
+
+/// TIMINGR register fields
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Timing {
+    pub presc: u8,
+    pub scldel: u8,
+    pub sdadel: u8,
+    pub sclh: u8,
+    pub scll: u8,
+}
+
+impl Timing {
+    /// Returns the TIMINGR register value
+    pub fn bits(&self) -> u32 {
+        (self.presc as u32) << 28
+            | (self.scldel as u32) << 20
+            | (self.sdadel as u32) << 16
+            | (self.sclh as u32) << 8
+            | self.scll as u32
+    }
+}
+
+/// Bus and filter parameters used for the timing calculation
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct TimingParams {
+    /// Requested SCL frequency, up to 1 MHz
+    pub speed: Hertz,
+    /// SCL/SDA rise time in ns
+    pub rise_time: u32,
+    /// SCL/SDA fall time in ns
+    pub fall_time: u32,
+    /// Analog filter enabled
+    pub analog_filter: bool,
+    /// Digital filter length in kernel clock cycles, 0 to 15
+    pub digital_filter: u8,
+}
+
+/// Computes the TIMINGR fields for the kernel clock `i2c_clk`
+pub fn calculate(i2c_clk: Hertz, params: &TimingParams) -> Result<Timing, TimingError> {
+    let speed = params.speed.raw();
+    let spec = if speed == 0 || i2c_clk.raw() == 0 {
+        return Err(TimingError::Unreachable);
+    } else if speed <= 100_000 {
+        &STANDARD
+    } else if speed <= 400_000 {
+        &FAST
+    } else if speed <= 1_000_000 {
+        &FAST_PLUS
+    } else {
+        return Err(TimingError::SpeedTooHigh);
+    };
+    if params.rise_time > spec.rise_max || params.fall_time > spec.fall_max {
+        return Err(TimingError::RiseFallTime);
+    }
+    if params.digital_filter > 15 {
+        return Err(TimingError::DigitalFilter);
+    }
+
+    // All times are handled in ps to keep the rounding of the clock periods small
+    let rise = ps(params.rise_time);
+    let fall = ps(params.fall_time);
+    let dnf = params.digital_filter as i64;
+    let i2cclk = div_round(1_000_000_000_000, i2c_clk.raw() as u64) as i64;
+    let i2cbus = div_round(1_000_000_000_000, speed as u64) as i64;
+
+    let (af_delay_min, af_delay_max) = if params.analog_filter {
+        (ps(ANALOG_FILTER_DELAY_MIN), ps(ANALOG_FILTER_DELAY_MAX))
+    } else {
+        (0, 0)
+    };
+    let dnf_delay = dnf * i2cclk;
+
+    let sdadel_min = (ps(spec.hddat_min) + fall - af_delay_min - (dnf + 3) * i2cclk).max(0);
+    let sdadel_max = ps(spec.vddat_max) - rise - af_delay_max - (dnf + 4) * i2cclk;
+    if sdadel_max < sdadel_min {
+        // The kernel clock is too slow for the data hold time of the bus mode
+        return Err(TimingError::Unreachable);
+    }
+    let scldel_min = rise + ps(spec.sudat_min);
+
+    let tsync = af_delay_min + dnf_delay + 2 * i2cclk;
+    // Accept frequencies down to 80% of the requested one
+    let clk_min = 1_000_000_000_000 / speed as i64;
+    let clk_max = 1_250_000_000_000 / speed as i64;
+
+    let mut best: Option<Timing> = None;
+    let mut best_error = i2cbus;
+
+    for presc in 0..PRESC_MAX {
+        let Some((scldel, sdadel)) = data_delays(presc, i2cclk, scldel_min, sdadel_min, sdadel_max)
+        else {
+            continue;
+        };
+
+        let prescaler = (presc as i64 + 1) * i2cclk;
+        // Shortest SCL high count meeting tHIGH, which must exceed one kernel clock
+        let sclh_count_min = div_ceil(ps(spec.h_min).max(i2cclk + 1) - tsync, prescaler).max(1);
+        if sclh_count_min > SCLH_MAX as i64 {
+            continue;
+        }
+        for scll in 0..SCLL_MAX {
+            let tscl_l = (scll as i64 + 1) * prescaler + tsync;
+            if tscl_l < ps(spec.l_min) || i2cclk >= (tscl_l - af_delay_min - dnf_delay) / 4 {
+                continue;
+            }
+            // SCL period without the SCL high count
+            let fixed = tscl_l + tsync + rise + fall;
+            if fixed + sclh_count_min * prescaler > clk_max {
+                // Longer low periods are slower still
+                break;
+            }
+
+            // The closest period is just below or above the requested one, or the
+            // shortest one accepted
+            let below = (i2cbus - fixed).div_euclid(prescaler);
+            let mut counts = [div_ceil(clk_min - fixed, prescaler), below, below + 1];
+            counts.sort_unstable();
+            for count in counts {
+                let count = count.clamp(sclh_count_min, SCLH_MAX as i64);
+                let tscl = fixed + count * prescaler;
+                if tscl >= clk_min && tscl <= clk_max {
+                    let error = (tscl - i2cbus).abs();
+                    if error < best_error {
+                        best_error = error;
+                        best = Some(Timing {
+                            presc: presc as u8,
+                            scldel: scldel as u8,
+                            sdadel: sdadel as u8,
+                            sclh: (count - 1) as u8,
+                            scll: scll as u8,
+                        });
+                    }
+                }
+            }
+        }
+    }
+
+    best.ok_or(TimingError::Unreachable)
+}
+
+/// Returns the first SCLDEL/SDADEL pair meeting the data setup and hold times
+fn data_delays(
+    presc: u32,
+    i2cclk: i64,
+    scldel_min: i64,
+    sdadel_min: i64,
+    sdadel_max: i64,
+) -> Option<(u32, u32)> {
+    let p = presc as i64 + 1;
+    for scldel in 0..SCLDEL_MAX {
+        if (scldel as i64 + 1) * p * i2cclk < scldel_min {
+            continue;
+        }
+        for sdadel in 0..SDADEL_MAX {
+            let t = sdadel as i64 * p * i2cclk;
+            if t >= sdadel_min && t <= sdadel_max {
+                return Some((scldel, sdadel));
+            }
+        }
+    }
+    None
+}
+
+fn ps(ns: u32) -> i64 {
+    ns as i64 * 1000
+}
+
+fn div_round(n: u64, d: u64) -> u64 {
+    (n + d / 2) / d
+}
+
+fn div_ceil(n: i64, d: i64) -> i64 {
+    -(-n).div_euclid(d)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn params(speed: u32) -> TimingParams {
+        TimingParams {
+            speed: Hertz::from_raw(speed),
+            rise_time: DEFAULT_RISE_TIME,
+            fall_time: DEFAULT_FALL_TIME,
+            analog_filter: true,
+            digital_filter: 0,
+        }
+    }
+
+    fn timing(mhz: u32, params: &TimingParams) -> Result<Timing, TimingError> {
+        calculate(Hertz::from_raw(mhz * 1_000_000), params)
+    }
+
+    /// Checks the register values against the bus specification, times in ps
+    fn check_spec(mhz: u32, params: &TimingParams, t: &Timing) {
+        let speed = params.speed.raw() as i64;
+        let spec = match speed {
+            0..=100_000 => &STANDARD,
+            100_001..=400_000 => &FAST,
+            _ => &FAST_PLUS,
+        };
+        let i2cclk = div_round(1_000_000, mhz as u64) as i64;
+        let presc = (t.presc as i64 + 1) * i2cclk;
+        let af = if params.analog_filter {
+            ps(ANALOG_FILTER_DELAY_MIN)
+        } else {
+            0
+        };
+        let tsync = af + params.digital_filter as i64 * i2cclk + 2 * i2cclk;
+        let low = (t.scll as i64 + 1) * presc + tsync;
+        let high = (t.sclh as i64 + 1) * presc + tsync;
+        let period = low + high + ps(params.rise_time) + ps(params.fall_time);
+
+        assert!(low >= ps(spec.l_min), "tLOW {} ps", low);
+        assert!(high >= ps(spec.h_min), "tHIGH {} ps", high);
+        assert!(period >= 1_000_000_000_000 / speed, "{} Hz too fast", speed);
+        assert!(period <= 1_250_000_000_000 / speed, "{} Hz too slow", speed);
+        let sudat = (t.scldel as i64 + 1) * presc;
+        assert!(sudat >= ps(params.rise_time) + ps(spec.sudat_min));
+        let hddat = t.sdadel as i64 * presc;
+        assert!(hddat <= ps(spec.vddat_max) - ps(params.rise_time));
+    }
+
+    #[test]
+    fn known_timings() {
+        let vectors = [
+            (16, 100_000, 0x0040_5048),
+            (16, 400_000, 0x0010_0f11),
+            (48, 100_000, 0x00d0_f7dd),
+            (48, 400_000, 0x0060_323a),
+            (48, 1_000_000, 0x0030_1113),
+            (64, 100_000, 0x1080_a593),
+            (64, 400_000, 0x0070_454d),
+            (64, 1_000_000, 0x0040_181a),
+        ];
+        for (mhz, speed, bits) in vectors {
+            let params = params(speed);
+            let t = timing(mhz, &params).unwrap();
+            assert_eq!(t.bits(), bits, "{} MHz, {} Hz", mhz, speed);
+            check_spec(mhz, &params, &t);
+        }
+    }
+
+    #[test]
+    fn reference_manual_examples() {
+        // RM0444 TIMINGR examples at 16 MHz, with the maximum rise and fall times
+        // of the bus mode they're given for
+        let vectors = [
+            (10_000, 1000, 300, 0x3042_c3c7),
+            (100_000, 1000, 300, 0x3042_0f13),
+            (400_000, 300, 300, 0x1032_0309),
+        ];
+        for (speed, rise_time, fall_time, bits) in vectors {
+            let mut params = params(speed);
+            params.rise_time = rise_time;
+            params.fall_time = fall_time;
+            let example = Timing {
+                presc: (bits >> 28) as u8,
+                scldel: (bits >> 20 & 0xf) as u8,
+                sdadel: (bits >> 16 & 0xf) as u8,
+                sclh: (bits >> 8) as u8,
+                scll: bits as u8,
+            };
+            check_spec(16, &params, &example);
+            check_spec(16, &params, &timing(16, &params).unwrap());
+        }
+    }
+
+    #[test]
+    fn filters() {
+        let mut params = params(400_000);
+        params.analog_filter = false;
+        assert_eq!(timing(64, &params).unwrap().bits(), 0x0070_4751);
+
+        let mut params = self::params(400_000);
+        params.digital_filter = 4;
+        let t = timing(64, &params).unwrap();
+        assert_eq!(t.bits(), 0x0070_4149);
+        check_spec(64, &params, &t);
+    }
+
+    #[test]
+    fn spec_compliant() {
+        for mhz in [2, 8, 12, 16, 24, 32, 48, 56, 64] {
+            for speed in [
+                10_000, 50_000, 100_000, 250_000, 400_000, 700_000, 1_000_000,
+            ] {
+                let params = params(speed);
+                if let Ok(t) = timing(mhz, &params) {
+                    check_spec(mhz, &params, &t);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn speed_too_high() {
+        assert_eq!(
+            timing(64, &params(1_000_001)),
+            Err(TimingError::SpeedTooHigh)
+        );
+    }
+
+    #[test]
+    fn rise_fall_time() {
+        let mut params = params(1_000_000);
+        params.rise_time = 121;
+        assert_eq!(timing(64, &params), Err(TimingError::RiseFallTime));
+    }
+
+    #[test]
+    fn digital_filter() {
+        let mut params = params(100_000);
+        params.digital_filter = 16;
+        assert_eq!(timing(64, &params), Err(TimingError::DigitalFilter));
+    }
+
+    #[test]
+    fn clock_too_slow() {
+        // The data valid time leaves no data hold window at these kernel clocks
+        assert_eq!(
+            timing(16, &params(1_000_000)),
+            Err(TimingError::Unreachable)
+        );
+        assert_eq!(timing(4, &params(400_000)), Err(TimingError::Unreachable));
+        assert_eq!(timing(1, &params(100_000)), Err(TimingError::Unreachable));
+        assert_eq!(timing(0, &params(100_000)), Err(TimingError::Unreachable));
+        assert_eq!(timing(64, &params(0)), Err(TimingError::Unreachable));
+    }
+}
@@ -15,6 +15,23 @@ pub enum LowPowerMode {
     Shutdown = 0b111,
 }
 
+/// Low power modes keeping the peripherals powered
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum StopMode {
+    StopMode1,
+    StopMode2,
+}
+
+impl From<StopMode> for LowPowerMode {
+    fn from(mode: StopMode) -> Self {
+        match mode {
+            StopMode::StopMode1 => LowPowerMode::StopMode1,
+            StopMode::StopMode2 => LowPowerMode::StopMode2,
+        }
+    }
+}
+
 pub enum PowerMode {
     Run,
     LowPower(LowPowerMode),
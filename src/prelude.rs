@@ -1,18 +1,53 @@
 #[cfg(any(feature = "stm32g041", feature = "stm32g081"))]
 pub use crate::aes::AesExt as _;
 pub use crate::analog::adc::AdcExt as _;
-#[cfg(any(feature = "stm32g071", feature = "stm32g081"))]
+#[cfg(any(
+    feature = "stm32g071",
+    feature = "stm32g081",
+    feature = "stm32g0b1",
+    feature = "stm32g0c1"
+))]
 pub use crate::analog::comparator::ComparatorExt as _;
-#[cfg(any(feature = "stm32g071", feature = "stm32g081"))]
+#[cfg(any(
+    feature = "stm32g071",
+    feature = "stm32g081",
+    feature = "stm32g0b1",
+    feature = "stm32g0c1"
+))]
 pub use crate::analog::comparator::ComparatorSplit as _;
-#[cfg(any(feature = "stm32g071", feature = "stm32g081"))]
+#[cfg(any(
+    feature = "stm32g071",
+    feature = "stm32g081",
+    feature = "stm32g0b1",
+    feature = "stm32g0c1"
+))]
 pub use crate::analog::comparator::WindowComparatorExt as _;
-#[cfg(any(feature = "stm32g071", feature = "stm32g081"))]
+#[cfg(any(
+    feature = "stm32g071",
+    feature = "stm32g081",
+    feature = "stm32g0b1",
+    feature = "stm32g0c1"
+))]
 pub use crate::analog::dac::DacExt as _;
-#[cfg(any(feature = "stm32g071", feature = "stm32g081"))]
+#[cfg(any(
+    feature = "stm32g071",
+    feature = "stm32g081",
+    feature = "stm32g0b1",
+    feature = "stm32g0c1"
+))]
 pub use crate::analog::dac::DacOut as _;
+#[cfg(any(
+    feature = "stm32g071",
+    feature = "stm32g081",
+    feature = "stm32g0b1",
+    feature = "stm32g0c1"
+))]
+pub use crate::analog::vrefbuf::VrefBufExt as _;
 pub use crate::crc::CrcExt as _;
+pub use crate::timer::capture::InputCaptureExt as _;
+pub use crate::timer::capture::PwmInputExt as _;
 pub use crate::timer::delay::DelayExt as _;
+pub use crate::timer::hall::HallSensorExt as _;
 pub use hal::digital::*;
 // pub use crate::dma::CopyDma as _;
 pub use crate::dma::DmaExt as _;
@@ -37,6 +72,7 @@ pub use crate::serial::SerialExt as _;
 pub use crate::spi::SpiExt as _;
 pub use crate::time::U32Ext as _;
 pub use crate::timer::opm::OpmExt as _;
+pub use crate::timer::pulse_train::PulseTrainExt as _;
 pub use crate::timer::pwm::PwmExt as _;
 pub use crate::timer::pwm::PwmQExt as _;
 pub use crate::timer::qei::QeiExt as _;
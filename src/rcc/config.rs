@@ -47,6 +47,24 @@ pub enum LSCOSrc {
     LSE,
 }
 
+/// I2C kernel clock source
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum I2CSrc {
+    PCLK = 0b00,
+    SysClk = 0b01,
+    HSI = 0b10,
+}
+
+/// ADC kernel clock source
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ADCSrc {
+    SysClk = 0b00,
+    PLLP = 0b01,
+    HSI = 0b10,
+}
+
 /// PLL clock input source
 #[derive(Clone, Copy)]
 pub enum PLLSrc {
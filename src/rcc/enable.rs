@@ -173,11 +173,20 @@ bus! {
 #[cfg(any(feature = "stm32g071", feature = "stm32g081"))]
 bus! {
     HDMI_CEC => (APB1, cecen, cecsmen, cecrst), // 24
-    DAC => (APB1, dac1en, dac1smen, dac1rst), // 29
     UCPD1 => (APB1, ucpd1en, ucpd1smen, ucpd1rst), // 25
     UCPD2 => (APB1, ucpd2en, ucpd2smen, ucpd2rst), // 26
 }
 
+#[cfg(any(
+    feature = "stm32g071",
+    feature = "stm32g081",
+    feature = "stm32g0b1",
+    feature = "stm32g0c1"
+))]
+bus! {
+    DAC => (APB1, dac1en, dac1smen, dac1rst), // 29
+}
+
 #[cfg(feature = "stm32g0x1")]
 bus! {
     LPTIM1 => (APB1, lptim1en, lptim1smen, lptim1rst), // 31
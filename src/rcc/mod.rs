@@ -279,6 +279,53 @@ impl Rcc {
         PLLClocks { r, q, p }
     }
 
+    /// Selects the I2C1 kernel clock source and returns its frequency
+    pub fn set_i2c1_clock(&mut self, src: I2CSrc) -> Hertz {
+        let freq = match src {
+            I2CSrc::PCLK => self.clocks.apb_clk,
+            I2CSrc::SysClk => self.clocks.sys_clk,
+            I2CSrc::HSI => {
+                self.enable_hsi();
+                HSI_FREQ.Hz()
+            }
+        };
+        self.ccipr()
+            .modify(|_, w| unsafe { w.i2c1sel().bits(src as u8) });
+        freq
+    }
+
+    /// Selects the I2C2 kernel clock source and returns its frequency
+    #[cfg(any(feature = "stm32g0b1", feature = "stm32g0c1"))]
+    pub fn set_i2c2_clock(&mut self, src: I2CSrc) -> Hertz {
+        let freq = match src {
+            I2CSrc::PCLK => self.clocks.apb_clk,
+            I2CSrc::SysClk => self.clocks.sys_clk,
+            I2CSrc::HSI => {
+                self.enable_hsi();
+                HSI_FREQ.Hz()
+            }
+        };
+        self.ccipr()
+            .modify(|_, w| unsafe { w.i2c2sel().bits(src as u8) });
+        freq
+    }
+
+    /// Selects the ADC asynchronous kernel clock source and returns its frequency.
+    /// HSI keeps the ADC clocked at 16 MHz during low-power run.
+    pub fn set_adc_clock(&mut self, src: ADCSrc) -> Hertz {
+        let freq = match src {
+            ADCSrc::SysClk => self.clocks.sys_clk,
+            ADCSrc::PLLP => self.clocks.pll_clk.p.expect("PLLP is not enabled"),
+            ADCSrc::HSI => {
+                self.enable_hsi();
+                HSI_FREQ.Hz()
+            }
+        };
+        self.ccipr()
+            .modify(|_, w| unsafe { w.adcsel().bits(src as u8) });
+        freq
+    }
+
     pub(crate) fn enable_hsi(&self) {
         self.cr().modify(|_, w| w.hsion().set_bit());
         while self.cr().read().hsirdy().bit_is_clear() {}
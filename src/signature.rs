@@ -0,0 +1,124 @@
+//! Device electronic signature
+//!
+//! (stored in flash memory)
+//!
+//! The STM32G0 datasheets only document one temperature sensor calibration
+//! value, TS_CAL1 at 30°C, so there is no TS_CAL2.
+use core::str::from_utf8_unchecked;
+
+/// This is the test voltage, in millivolts of the calibration done at the factory
+pub const VDDA_CALIB: u32 = 3000;
+
+macro_rules! define_ptr_type {
+    ($name: ident, $ptr: expr) => {
+        impl $name {
+            fn ptr() -> *const Self {
+                $ptr as *const _
+            }
+
+            /// Returns a wrapped reference to the value in flash memory
+            pub fn get() -> &'static Self {
+                unsafe { &*Self::ptr() }
+            }
+        }
+    };
+}
+
+/// Unique Device ID register
+#[derive(Hash, Debug)]
+#[repr(C)]
+pub struct Uid {
+    x: u16,
+    y: u16,
+    waf_num: u8,
+    lot_num: [u8; 7],
+}
+define_ptr_type!(Uid, 0x1FFF_7590);
+
+impl Uid {
+    /// X coordinate on wafer
+    pub fn x(&self) -> u16 {
+        self.x
+    }
+
+    /// Y coordinate on wafer
+    pub fn y(&self) -> u16 {
+        self.y
+    }
+
+    /// Wafer number
+    pub fn waf_num(&self) -> u8 {
+        self.waf_num
+    }
+
+    /// Lot number
+    pub fn lot_num(&self) -> &str {
+        unsafe { from_utf8_unchecked(&self.lot_num) }
+    }
+
+    /// Returns the 96-bit unique ID as bytes, least significant first
+    pub fn bytes(&self) -> [u8; 12] {
+        unsafe { core::ptr::read_volatile(Self::ptr() as *const [u8; 12]) }
+    }
+}
+
+/// Size of integrated flash
+#[derive(Debug)]
+#[repr(C)]
+pub struct FlashSize(u16);
+define_ptr_type!(FlashSize, 0x1FFF_75E0);
+
+impl FlashSize {
+    /// Read flash size in kilobytes
+    pub fn kilo_bytes(&self) -> u16 {
+        self.0
+    }
+
+    /// Read flash size in bytes
+    pub fn bytes(&self) -> usize {
+        usize::from(self.kilo_bytes()) * 1024
+    }
+}
+
+/// Package data register
+#[derive(Debug)]
+#[repr(C)]
+pub struct Package(u16);
+define_ptr_type!(Package, 0x1FFF_7500);
+
+impl Package {
+    /// Returns the package code (PKG), see the reference manual for the encoding
+    /// of the device
+    pub fn code(&self) -> u8 {
+        (self.0 & 0x1f) as u8
+    }
+}
+
+/// ADC VREF calibration value stored at the factory
+#[derive(Debug)]
+#[repr(C)]
+pub struct VrefCal(u16);
+define_ptr_type!(VrefCal, 0x1FFF_75AA);
+
+impl VrefCal {
+    /// Read calibration value, raw reading of VREFINT at VDDA = 3.0 V
+    pub fn read(&self) -> u16 {
+        self.0
+    }
+}
+
+/// A temperature reading taken at 30°C stored at the factory
+#[derive(Debug)]
+#[repr(C)]
+pub struct TsCal1(u16);
+define_ptr_type!(TsCal1, 0x1FFF_75A8);
+
+impl TsCal1 {
+    /// Calibration temperature in °C
+    pub const TEMP: i32 = 30;
+
+    /// Read calibration value, raw reading at VDDA = 3.0 V
+    pub fn read(&self) -> u16 {
+        self.0
+    }
+}
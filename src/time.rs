@@ -1,6 +1,7 @@
 pub use fugit::{
     ExtU32, HertzU32 as Hertz, HoursDurationU32 as Hour, MicrosDurationU32 as MicroSecond,
-    MinutesDurationU32 as Minute, RateExtU32, SecsDurationU32 as Second,
+    MinutesDurationU32 as Minute, NanosDurationU32 as NanoSecond, RateExtU32,
+    SecsDurationU32 as Second,
 };
 
 /// Baudrate
@@ -113,6 +114,13 @@ pub fn duration(hz: Hertz, cycles: u32) -> MicroSecond {
     MicroSecond::from_ticks(us as u32)
 }
 
+pub fn duration_ns(hz: Hertz, cycles: u32) -> NanoSecond {
+    let cycles = cycles as u64;
+    let clk = hz.raw() as u64;
+    let ns = cycles.saturating_mul(1_000_000_000_u64) / clk;
+    NanoSecond::from_ticks(ns.min(u32::MAX as u64) as u32)
+}
+
 pub fn cycles(ms: MicroSecond, clk: Hertz) -> u32 {
     assert!(ms.ticks() > 0);
     let clk = clk.raw() as u64;
@@ -0,0 +1,530 @@
+//! # Input Capture
+//!
+//! [`InputCapture`] latches the timer counter into the channel capture register on
+//! edges of the bound pins. [`PwmInput`] combines the first two channels with the
+//! slave mode controller in reset mode to measure the period and the pulse width of
+//! a PWM signal on a single pin.
+use core::marker::PhantomData;
+
+use crate::dma;
+use crate::dmamux::DmaMuxIndex;
+use crate::gpio::SignalEdge;
+use crate::rcc::*;
+use crate::stm32::*;
+use crate::time::{duration_ns, Hertz, NanoSecond};
+use crate::timer::pins::TimerPin;
+use crate::timer::*;
+
+/// Input capture error
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Error {
+    /// A capture happened before the previous one was read
+    Overcapture,
+}
+
+/// Number of edges needed for a capture
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum CapturePrescaler {
+    /// Capture on every edge
+    NoDivision = 0,
+    /// Capture once every 2 edges
+    Div2 = 1,
+    /// Capture once every 4 edges
+    Div4 = 2,
+    /// Capture once every 8 edges
+    Div8 = 3,
+}
+
+pub struct InputCapture<TIM> {
+    clk: Hertz,
+    tim: TIM,
+}
+
+pub struct CapturePin<TIM, CH> {
+    tim: PhantomData<TIM>,
+    channel: PhantomData<CH>,
+}
+
+pub trait InputCaptureExt: Sized {
+    /// Configures the timer for input capture, counting at the `tick` rate
+    fn input_capture(self, tick: Hertz, rcc: &mut Rcc) -> InputCapture<Self>;
+}
+
+impl<TIM> InputCapture<TIM> {
+    pub fn bind_pin<PIN>(&self, pin: PIN) -> CapturePin<TIM, PIN::Channel>
+    where
+        PIN: TimerPin<TIM>,
+    {
+        pin.setup();
+        CapturePin {
+            tim: PhantomData,
+            channel: PhantomData,
+        }
+    }
+
+    /// Returns the counter clock, the resolution of the captured values
+    pub fn tick(&self) -> Hertz {
+        self.clk
+    }
+}
+
+/// PWM input channel, selects which capture registers hold the period and the pulse
+pub trait PwmInputChannel {
+    #[doc(hidden)]
+    const CC1S: u8;
+    #[doc(hidden)]
+    const CC2S: u8;
+    #[doc(hidden)]
+    const TS: u8;
+}
+
+impl PwmInputChannel for Channel1 {
+    // IC1 on TI1 measures the period, IC2 on TI1 the pulse, reset on TI1FP1
+    const CC1S: u8 = 0b01;
+    const CC2S: u8 = 0b10;
+    const TS: u8 = 0b101;
+}
+
+impl PwmInputChannel for Channel2 {
+    // IC2 on TI2 measures the period, IC1 on TI2 the pulse, reset on TI2FP2
+    const CC1S: u8 = 0b10;
+    const CC2S: u8 = 0b01;
+    const TS: u8 = 0b110;
+}
+
+/// PWM signal measurement on channel 1 or 2
+pub struct PwmInput<TIM, PIN> {
+    clk: Hertz,
+    tim: TIM,
+    pin: PIN,
+}
+
+pub trait PwmInputExt<PIN>: Sized {
+    /// Measures the PWM signal on `pin`, counting at the `tick` rate.
+    ///
+    /// The longest measurable period is 65536 ticks, 2^32 ticks on TIM2.
+    fn pwm_input(self, pin: PIN, tick: Hertz, rcc: &mut Rcc) -> PwmInput<Self, PIN>;
+}
+
+fn prescaler(clk: Hertz, tick: Hertz) -> u32 {
+    assert!(tick <= clk);
+    let psc = clk.raw() / tick.raw() - 1;
+    assert!(psc <= 0xffff);
+    psc
+}
+
+macro_rules! capture {
+    ($($TIMX:ident: ($timX:ident, $depth:ident),)+) => {
+        $(
+            impl InputCaptureExt for $TIMX {
+                fn input_capture(self, tick: Hertz, rcc: &mut Rcc) -> InputCapture<Self> {
+                    InputCapture::$timX(self, tick, rcc)
+                }
+            }
+
+            impl InputCapture<$TIMX> {
+                fn $timX(tim: $TIMX, tick: Hertz, rcc: &mut Rcc) -> Self {
+                    $TIMX::enable(rcc);
+                    $TIMX::reset(rcc);
+
+                    let mut capture = InputCapture {
+                        clk: rcc.clocks.apb_tim_clk,
+                        tim,
+                    };
+                    capture.set_tick(tick);
+                    capture.tim.arr().write(|w| unsafe { w.bits($depth::MAX as u32) });
+                    capture.tim.egr().write(|w| w.ug().set_bit());
+                    capture.tim.cr1().modify(|_, w| w.cen().set_bit());
+                    capture
+                }
+
+                /// Changes the counter clock. Actual rate may differ from requested
+                /// due to the prescaler resolution, check it with `tick`.
+                pub fn set_tick(&mut self, tick: Hertz) {
+                    let clk = self.clk.raw() * (self.tim.psc().read().bits() + 1);
+                    let psc = prescaler(Hertz::from_raw(clk), tick);
+                    self.tim.psc().write(|w| unsafe { w.bits(psc) });
+                    self.clk = Hertz::from_raw(clk / (psc + 1));
+                    self.tim.egr().write(|w| w.ug().set_bit());
+                }
+
+                /// Returns the counter value
+                pub fn counter(&self) -> u32 {
+                    self.tim.cnt().read().bits()
+                }
+
+                /// Converts a difference between two captures to a duration
+                pub fn duration(&self, ticks: u32) -> NanoSecond {
+                    duration_ns(self.clk, ticks)
+                }
+
+                pub fn pause(&mut self) {
+                    self.tim.cr1().modify(|_, w| w.cen().clear_bit());
+                }
+
+                pub fn resume(&mut self) {
+                    self.tim.cr1().modify(|_, w| w.cen().set_bit());
+                }
+
+                /// Releases the TIM peripheral
+                pub fn release(self) -> $TIMX {
+                    self.tim
+                }
+            }
+        )+
+    }
+}
+
+macro_rules! capture_hal {
+    ($($TIMX:ident: (
+        $CH:ty,
+        $ccmrx_input:ident,
+        $ccxs:ident,
+        $icxpsc:ident,
+        $icxf:ident,
+        $ccxe:ident,
+        $ccxp:ident,
+        $ccxnp:ident,
+        $ccxie:ident,
+        $ccxde:ident,
+        $ccxif:ident,
+        $ccxof:ident,
+        $ccrx:ident $(($idx:literal))*
+        $(, $dmamux:ident)*
+    ),)+) => {
+        $(
+            impl CapturePin<$TIMX, $CH> {
+                /// Starts capturing the input of the channel
+                pub fn enable(&mut self) {
+                    let tim = unsafe { &*$TIMX::ptr() };
+                    tim.$ccmrx_input().modify(|_, w| unsafe { w.$ccxs().bits(0b01) });
+                    tim.ccer().modify(|_, w| w.$ccxe().set_bit());
+                }
+
+                pub fn disable(&mut self) {
+                    let tim = unsafe { &*$TIMX::ptr() };
+                    tim.ccer().modify(|_, w| w.$ccxe().clear_bit());
+                }
+
+                /// Selects the edges triggering a capture
+                pub fn set_edge(&mut self, edge: SignalEdge) {
+                    let (p, np) = match edge {
+                        SignalEdge::Rising => (false, false),
+                        SignalEdge::Falling => (true, false),
+                        SignalEdge::All => (true, true),
+                    };
+                    let tim = unsafe { &*$TIMX::ptr() };
+                    tim.ccer().modify(|_, w| w.$ccxp().bit(p).$ccxnp().bit(np));
+                }
+
+                pub fn set_prescaler(&mut self, prescaler: CapturePrescaler) {
+                    let tim = unsafe { &*$TIMX::ptr() };
+                    tim.$ccmrx_input().modify(|_, w| unsafe { w.$icxpsc().bits(prescaler as u8) });
+                }
+
+                /// Sets the input filter, see ICxF in the reference manual
+                pub fn set_filter(&mut self, capture_filter: u8) {
+                    assert!(capture_filter < 16);
+                    let tim = unsafe { &*$TIMX::ptr() };
+                    tim.$ccmrx_input().modify(|_, w| unsafe { w.$icxf().bits(capture_filter) });
+                }
+
+                /// Starts listening for captures
+                pub fn listen(&mut self) {
+                    let tim = unsafe { &*$TIMX::ptr() };
+                    tim.dier().modify(|_, w| w.$ccxie().set_bit());
+                }
+
+                /// Stops listening for captures
+                pub fn unlisten(&mut self) {
+                    let tim = unsafe { &*$TIMX::ptr() };
+                    tim.dier().modify(|_, w| w.$ccxie().clear_bit());
+                }
+
+                /// Clears the capture and overcapture flags
+                pub fn clear_irq(&mut self) {
+                    let tim = unsafe { &*$TIMX::ptr() };
+                    tim.sr().modify(|_, w| w.$ccxif().clear_bit().$ccxof().clear_bit());
+                }
+
+                /// Returns the last captured counter value
+                pub fn get_capture(&self) -> u32 {
+                    unsafe { (*$TIMX::ptr()).$ccrx($($idx)*).read().bits() }
+                }
+
+                /// Returns a new capture, or an error if a capture was missed
+                pub fn capture(&mut self) -> nb::Result<u32, Error> {
+                    let tim = unsafe { &*$TIMX::ptr() };
+                    let sr = tim.sr().read();
+                    if sr.$ccxif().bit_is_clear() {
+                        return Err(nb::Error::WouldBlock);
+                    }
+                    // Reading the capture register clears the capture flag
+                    let value = self.get_capture();
+                    if sr.$ccxof().bit_is_set() {
+                        tim.sr().modify(|_, w| w.$ccxof().clear_bit());
+                        return Err(nb::Error::Other(Error::Overcapture));
+                    }
+                    Ok(value)
+                }
+            }
+
+            $(
+                impl dma::Target for CapturePin<$TIMX, $CH> {
+                    fn dmamux(&self) -> DmaMuxIndex {
+                        DmaMuxIndex::$dmamux
+                    }
+
+                    fn enable_dma(&mut self) {
+                        let tim = unsafe { &*$TIMX::ptr() };
+                        tim.dier().modify(|_, w| w.$ccxde().set_bit());
+                    }
+
+                    fn disable_dma(&mut self) {
+                        let tim = unsafe { &*$TIMX::ptr() };
+                        tim.dier().modify(|_, w| w.$ccxde().clear_bit());
+                    }
+                }
+            )*
+        )+
+    };
+}
+
+macro_rules! pwm_input {
+    ($($TIMX:ident: (
+        $timX:ident,
+        $depth:ident,
+        $sms:ident,
+        $ts:ident,
+        $ccr1:ident $(($idx1:literal))*,
+        $ccr2:ident $(($idx2:literal))*
+    ),)+) => {
+        $(
+            impl<PIN> PwmInputExt<PIN> for $TIMX
+            where
+                PIN: TimerPin<$TIMX>,
+                PIN::Channel: PwmInputChannel,
+            {
+                fn pwm_input(self, pin: PIN, tick: Hertz, rcc: &mut Rcc) -> PwmInput<Self, PIN> {
+                    PwmInput::$timX(self, pin, tick, rcc)
+                }
+            }
+
+            impl<PIN> PwmInput<$TIMX, PIN>
+            where
+                PIN: TimerPin<$TIMX>,
+                PIN::Channel: PwmInputChannel,
+            {
+                fn $timX(tim: $TIMX, pin: PIN, tick: Hertz, rcc: &mut Rcc) -> Self {
+                    $TIMX::enable(rcc);
+                    $TIMX::reset(rcc);
+
+                    let clk = rcc.clocks.apb_tim_clk;
+                    let psc = prescaler(clk, tick);
+                    tim.psc().write(|w| unsafe { w.bits(psc) });
+                    tim.arr().write(|w| unsafe { w.bits($depth::MAX as u32) });
+
+                    // Both channels capture the same input, the channel of the pin
+                    // on rising edges and the other one on falling edges
+                    tim.ccmr1_input().write(|w| unsafe {
+                        w.cc1s()
+                            .bits(<PIN::Channel>::CC1S)
+                            .cc2s()
+                            .bits(<PIN::Channel>::CC2S)
+                    });
+                    let ch1_rising = <PIN::Channel>::CC1S == 0b01;
+                    tim.ccer().write(|w| {
+                        w.cc1p()
+                            .bit(!ch1_rising)
+                            .cc2p()
+                            .bit(ch1_rising)
+                            .cc1e()
+                            .set_bit()
+                            .cc2e()
+                            .set_bit()
+                    });
+
+                    // Reset the counter on the rising edge
+                    tim.smcr().write(|w| unsafe {
+                        w.$ts().bits(<PIN::Channel>::TS).$sms().bits(0b100)
+                    });
+
+                    pin.setup();
+
+                    // Only counter overflows set the update flag
+                    tim.cr1().write(|w| w.urs().set_bit());
+                    tim.egr().write(|w| w.ug().set_bit());
+                    tim.sr().reset();
+                    tim.cr1().modify(|_, w| w.cen().set_bit());
+
+                    PwmInput {
+                        clk: Hertz::from_raw(clk.raw() / (psc + 1)),
+                        tim,
+                        pin,
+                    }
+                }
+
+                /// Sets the input filter, see IC1F in the reference manual
+                pub fn set_filter(&mut self, capture_filter: u8) {
+                    assert!(capture_filter < 16);
+                    if <PIN::Channel>::CC1S == 0b01 {
+                        self.tim.ccmr1_input().modify(|_, w| unsafe { w.ic1f().bits(capture_filter) });
+                    } else {
+                        self.tim.ccmr1_input().modify(|_, w| unsafe { w.ic2f().bits(capture_filter) });
+                    }
+                }
+
+                /// Returns the counter clock, the resolution of the measurement
+                pub fn tick(&self) -> Hertz {
+                    self.clk
+                }
+
+                /// Returns the period and the pulse width in ticks of the last
+                /// complete cycle
+                pub fn ticks(&self) -> (u32, u32) {
+                    let ccr1 = self.tim.$ccr1($($idx1)*).read().bits();
+                    let ccr2 = self.tim.$ccr2($($idx2)*).read().bits();
+                    if <PIN::Channel>::CC1S == 0b01 {
+                        (ccr1, ccr2)
+                    } else {
+                        (ccr2, ccr1)
+                    }
+                }
+
+                /// Returns true when a new period was measured since the last call
+                pub fn is_ready(&mut self) -> bool {
+                    let sr = self.tim.sr().read();
+                    let ready = if <PIN::Channel>::CC1S == 0b01 {
+                        sr.cc1if().bit_is_set()
+                    } else {
+                        sr.cc2if().bit_is_set()
+                    };
+                    if ready {
+                        self.tim.sr().modify(|_, w| w.cc1if().clear_bit().cc2if().clear_bit());
+                    }
+                    ready
+                }
+
+                /// Returns true if the counter overflowed, the input signal is
+                /// stopped or slower than the longest measurable period
+                pub fn is_timeout(&mut self) -> bool {
+                    let timeout = self.tim.sr().read().uif().bit_is_set();
+                    if timeout {
+                        self.tim.sr().modify(|_, w| w.uif().clear_bit());
+                    }
+                    timeout
+                }
+
+                /// Returns the period of the signal
+                pub fn period(&self) -> NanoSecond {
+                    duration_ns(self.clk, self.ticks().0)
+                }
+
+                /// Returns the high time of the signal
+                pub fn pulse_width(&self) -> NanoSecond {
+                    duration_ns(self.clk, self.ticks().1)
+                }
+
+                /// Returns the frequency of the signal, 0 Hz until a period was measured
+                pub fn frequency(&self) -> Hertz {
+                    match self.ticks().0 {
+                        0 => Hertz::from_raw(0),
+                        period => Hertz::from_raw(self.clk.raw() / period),
+                    }
+                }
+
+                /// Starts listening for complete periods
+                pub fn listen(&mut self) {
+                    if <PIN::Channel>::CC1S == 0b01 {
+                        self.tim.dier().modify(|_, w| w.cc1ie().set_bit());
+                    } else {
+                        self.tim.dier().modify(|_, w| w.cc2ie().set_bit());
+                    }
+                }
+
+                /// Stops listening for complete periods
+                pub fn unlisten(&mut self) {
+                    self.tim.dier().modify(|_, w| w.cc1ie().clear_bit().cc2ie().clear_bit());
+                }
+
+                /// Releases the TIM peripheral and the pin
+                pub fn release(self) -> ($TIMX, PIN) {
+                    self.tim.cr1().modify(|_, w| w.cen().clear_bit());
+                    (self.tim, self.pin.release())
+                }
+            }
+        )+
+    }
+}
+
+capture! {
+    TIM1: (tim1, u16),
+    TIM3: (tim3, u16),
+    TIM14: (tim14, u16),
+    TIM16: (tim16, u16),
+    TIM17: (tim17, u16),
+}
+
+#[cfg(feature = "stm32g0x1")]
+capture! {
+    TIM2: (tim2, u32),
+}
+
+#[cfg(any(feature = "stm32g070", feature = "stm32g071", feature = "stm32g081"))]
+capture! {
+    TIM15: (tim15, u16),
+}
+
+capture_hal! {
+    TIM1: (Channel1, ccmr1_input, cc1s, ic1psc, ic1f, cc1e, cc1p, cc1np, cc1ie, cc1de, cc1if, cc1of, ccr(0), TIM1_CH1),
+    TIM1: (Channel2, ccmr1_input, cc2s, ic2psc, ic2f, cc2e, cc2p, cc2np, cc2ie, cc2de, cc2if, cc2of, ccr(1), TIM1_CH2),
+    TIM1: (Channel3, ccmr2_input, cc3s, ic3psc, ic3f, cc3e, cc3p, cc3np, cc3ie, cc3de, cc3if, cc3of, ccr(2), TIM1_CH3),
+    TIM1: (Channel4, ccmr2_input, cc4s, ic4psc, ic4f, cc4e, cc4p, cc4np, cc4ie, cc4de, cc4if, cc4of, ccr(3), TIM1_CH4),
+    TIM3: (Channel1, ccmr1_input, cc1s, ic1psc, ic1f, cc1e, cc1p, cc1np, cc1ie, cc1de, cc1if, cc1of, ccr1, TIM3_CH1),
+    TIM3: (Channel2, ccmr1_input, cc2s, ic2psc, ic2f, cc2e, cc2p, cc2np, cc2ie, cc2de, cc2if, cc2of, ccr2, TIM3_CH2),
+    TIM3: (Channel3, ccmr2_input, cc3s, ic3psc, ic3f, cc3e, cc3p, cc3np, cc3ie, cc3de, cc3if, cc3of, ccr3, TIM3_CH3),
+    TIM3: (Channel4, ccmr2_input, cc4s, ic4psc, ic4f, cc4e, cc4p, cc4np, cc4ie, cc4de, cc4if, cc4of, ccr4, TIM3_CH4),
+    TIM14: (Channel1, ccmr1_input, cc1s, ic1psc, ic1f, cc1e, cc1p, cc1np, cc1ie, cc1de, cc1if, cc1of, ccr(0)),
+    TIM16: (Channel1, ccmr1_input, cc1s, ic1psc, ic1f, cc1e, cc1p, cc1np, cc1ie, cc1de, cc1if, cc1of, ccr(0), TIM16_CH1),
+    TIM17: (Channel1, ccmr1_input, cc1s, ic1psc, ic1f, cc1e, cc1p, cc1np, cc1ie, cc1de, cc1if, cc1of, ccr(0), TIM17_CH1),
+}
+
+#[cfg(feature = "stm32g0x1")]
+capture_hal! {
+    TIM2: (Channel1, ccmr1_input, cc1s, ic1psc, ic1f, cc1e, cc1p, cc1np, cc1ie, cc1de, cc1if, cc1of, ccr1, TIM2_CH1),
+    TIM2: (Channel2, ccmr1_input, cc2s, ic2psc, ic2f, cc2e, cc2p, cc2np, cc2ie, cc2de, cc2if, cc2of, ccr2, TIM2_CH2),
+    TIM2: (Channel3, ccmr2_input, cc3s, ic3psc, ic3f, cc3e, cc3p, cc3np, cc3ie, cc3de, cc3if, cc3of, ccr3, TIM2_CH3),
+    TIM2: (Channel4, ccmr2_input, cc4s, ic4psc, ic4f, cc4e, cc4p, cc4np, cc4ie, cc4de, cc4if, cc4of, ccr4, TIM2_CH4),
+}
+
+#[cfg(any(feature = "stm32g070", feature = "stm32g071", feature = "stm32g081"))]
+capture_hal! {
+    TIM15: (Channel1, ccmr1_input, cc1s, ic1psc, ic1f, cc1e, cc1p, cc1np, cc1ie, cc1de, cc1if, cc1of, ccr(0), TIM15_CH1),
+    TIM15: (Channel2, ccmr1_input, cc2s, ic2psc, ic2f, cc2e, cc2p, cc2np, cc2ie, cc2de, cc2if, cc2of, ccr(1), TIM15_CH2),
+}
+
+#[cfg(not(any(feature = "stm32g0b1", feature = "stm32g0c1")))]
+pwm_input! {
+    TIM1: (tim1, u16, sms, ts, ccr(0), ccr(1)),
+    TIM3: (tim3, u16, sms, ts, ccr1, ccr2),
+}
+
+#[cfg(any(feature = "stm32g0b1", feature = "stm32g0c1"))]
+pwm_input! {
+    TIM1: (tim1, u16, sms1, ts1, ccr(0), ccr(1)),
+    TIM2: (tim2, u32, sms1, ts1, ccr1, ccr2),
+    TIM3: (tim3, u16, sms1, ts1, ccr1, ccr2),
+}
+
+#[cfg(not(any(feature = "stm32g0b1", feature = "stm32g0c1")))]
+#[cfg(feature = "stm32g0x1")]
+pwm_input! {
+    TIM2: (tim2, u32, sms, ts, ccr1, ccr2),
+}
+
+#[cfg(any(feature = "stm32g070", feature = "stm32g071", feature = "stm32g081"))]
+pwm_input! {
+    TIM15: (tim15, u16, sms1, ts, ccr(0), ccr(1)),
+}
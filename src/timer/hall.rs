@@ -0,0 +1,358 @@
+//! # Hall Sensor Interface and Six-Step Commutation
+//!
+//! [`HallSensor`] XORs the three hall sensor inputs into TI1 and captures the time
+//! between sensor edges. After a programmable delay it pulses its trigger output,
+//! which fires the commutation (COM) event of a [`SixStep`] controller on TIM1: the
+//! output states preloaded with [`SixStep::set_step`] are then applied all at once.
+//!
+//! Hall pins in an input mode can still be read with `pins` to find the rotor sector.
+use core::marker::PhantomData;
+
+use crate::rcc::*;
+use crate::stm32::*;
+use crate::time::{duration_ns, Hertz, NanoSecond};
+use crate::timer::pins::TimerPin;
+use crate::timer::pwm::{OutputCompareMode, Pwm};
+use crate::timer::sync::InternalTrigger;
+use crate::timer::*;
+
+pub struct HallSensor<TIM, PINS> {
+    clk: Hertz,
+    tim: TIM,
+    pins: PINS,
+}
+
+pub trait HallPins<TIM> {
+    fn setup(&self);
+    fn release(self) -> Self;
+}
+
+impl<TIM, P1, P2, P3> HallPins<TIM> for (P1, P2, P3)
+where
+    P1: TimerPin<TIM, Channel = Channel1>,
+    P2: TimerPin<TIM, Channel = Channel2>,
+    P3: TimerPin<TIM, Channel = Channel3>,
+{
+    fn setup(&self) {
+        self.0.setup();
+        self.1.setup();
+        self.2.setup();
+    }
+
+    fn release(self) -> Self {
+        (self.0.release(), self.1.release(), self.2.release())
+    }
+}
+
+pub trait HallSensorExt<PINS>: Sized {
+    /// Configures the timer as hall sensor interface counting at the `tick` rate
+    fn hall_sensor(self, pins: PINS, tick: Hertz, rcc: &mut Rcc) -> HallSensor<Self, PINS>;
+}
+
+macro_rules! hall_sensor {
+    ($($TIMX:ident: ($tim:ident, $depth:ident, $sms:ident, $ts:ident, $ccr1:ident, $ccr2:ident),)+) => {
+        $(
+            impl<PINS> HallSensorExt<PINS> for $TIMX where PINS: HallPins<$TIMX> {
+                fn hall_sensor(self, pins: PINS, tick: Hertz, rcc: &mut Rcc) -> HallSensor<$TIMX, PINS> {
+                    HallSensor::$tim(self, pins, tick, rcc)
+                }
+            }
+
+            impl<PINS> HallSensor<$TIMX, PINS> where PINS: HallPins<$TIMX> {
+                fn $tim(tim: $TIMX, pins: PINS, tick: Hertz, rcc: &mut Rcc) -> Self {
+                    $TIMX::enable(rcc);
+                    $TIMX::reset(rcc);
+
+                    let clk = rcc.clocks.apb_tim_clk;
+                    assert!(tick <= clk);
+                    let psc = clk.raw() / tick.raw() - 1;
+                    assert!(psc <= 0xffff);
+                    tim.psc().write(|w| unsafe { w.bits(psc) });
+                    tim.arr().write(|w| unsafe { w.bits($depth::MAX as u32) });
+
+                    // TI1 is the XOR of the three inputs, its edges are captured on
+                    // channel 1 through TRC and reset the counter
+                    tim.cr2().write(|w| unsafe { w.ti1s().set_bit().mms().bits(0b101) });
+                    tim.ccmr1_input().write(|w| unsafe { w.cc1s().bits(0b11) });
+                    // Channel 2 delays the commutation trigger output (OC2REF)
+                    tim.ccmr1_output().modify(|_, w| unsafe { w.oc2m().bits(7) });
+                    tim.$ccr2().write(|w| unsafe { w.bits(1) });
+                    tim.ccer().write(|w| w.cc1e().set_bit());
+                    tim.smcr().write(|w| unsafe { w.$ts().bits(0b100).$sms().bits(0b100) });
+
+                    pins.setup();
+
+                    // Only counter overflows set the update flag
+                    tim.cr1().write(|w| w.urs().set_bit());
+                    tim.egr().write(|w| w.ug().set_bit());
+                    tim.sr().reset();
+                    tim.cr1().modify(|_, w| w.cen().set_bit());
+
+                    HallSensor {
+                        clk: Hertz::from_raw(clk.raw() / (psc + 1)),
+                        tim,
+                        pins,
+                    }
+                }
+
+                /// Sets the delay in ticks between a hall edge and the commutation
+                /// trigger output
+                pub fn set_commutation_delay(&mut self, delay: u16) {
+                    self.tim.$ccr2().write(|w| unsafe { w.bits(delay.max(1) as u32) });
+                }
+
+                /// Sets the input filter, see IC1F in the reference manual
+                pub fn set_filter(&mut self, capture_filter: u8) {
+                    assert!(capture_filter < 16);
+                    self.tim.ccmr1_input().modify(|_, w| unsafe { w.ic1f().bits(capture_filter) });
+                }
+
+                /// Returns the counter clock, the resolution of the intervals
+                pub fn tick(&self) -> Hertz {
+                    self.clk
+                }
+
+                /// Returns the last interval between two hall edges in ticks
+                pub fn interval_ticks(&self) -> u32 {
+                    self.tim.$ccr1().read().bits()
+                }
+
+                /// Returns the last interval between two hall edges
+                pub fn interval(&self) -> NanoSecond {
+                    duration_ns(self.clk, self.interval_ticks())
+                }
+
+                /// Returns true when a hall edge was captured since the last call
+                pub fn is_edge(&mut self) -> bool {
+                    let edge = self.tim.sr().read().cc1if().bit_is_set();
+                    if edge {
+                        self.tim.sr().modify(|_, w| w.cc1if().clear_bit());
+                    }
+                    edge
+                }
+
+                /// Returns true if the counter overflowed, the motor is stopped or
+                /// slower than the longest measurable interval
+                pub fn is_timeout(&mut self) -> bool {
+                    let timeout = self.tim.sr().read().uif().bit_is_set();
+                    if timeout {
+                        self.tim.sr().modify(|_, w| w.uif().clear_bit());
+                    }
+                    timeout
+                }
+
+                /// Starts listening for hall edges
+                pub fn listen(&mut self) {
+                    self.tim.dier().modify(|_, w| w.cc1ie().set_bit());
+                }
+
+                /// Stops listening for hall edges
+                pub fn unlisten(&mut self) {
+                    self.tim.dier().modify(|_, w| w.cc1ie().clear_bit());
+                }
+
+                /// Clears interrupt flag
+                pub fn clear_irq(&mut self) {
+                    self.tim.sr().modify(|_, w| w.cc1if().clear_bit());
+                }
+
+                /// Returns the hall sensor pins
+                pub fn pins(&self) -> &PINS {
+                    &self.pins
+                }
+
+                /// Releases the TIM peripheral and the pins
+                pub fn release(self) -> ($TIMX, PINS) {
+                    self.tim.cr1().modify(|_, w| w.cen().clear_bit());
+                    (self.tim, self.pins.release())
+                }
+            }
+        )+
+    };
+}
+
+#[cfg(not(any(feature = "stm32g0b1", feature = "stm32g0c1")))]
+hall_sensor! {
+    TIM3: (tim3, u16, sms, ts, ccr1, ccr2),
+}
+
+#[cfg(not(any(feature = "stm32g0b1", feature = "stm32g0c1")))]
+#[cfg(feature = "stm32g0x1")]
+hall_sensor! {
+    TIM2: (tim2, u32, sms, ts, ccr1, ccr2),
+}
+
+#[cfg(any(feature = "stm32g0b1", feature = "stm32g0c1"))]
+hall_sensor! {
+    TIM2: (tim2, u32, sms1, ts1, ccr1, ccr2),
+    TIM3: (tim3, u16, sms1, ts1, ccr1, ccr2),
+}
+
+/// Motor phase, driven by TIM1 channel 1, 2 or 3 and its complementary output
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Phase {
+    U = 0,
+    V = 1,
+    W = 2,
+}
+
+/// Six-step commutation on TIM1 channels 1 to 3
+pub struct SixStep<HALL> {
+    pwm: Pwm<TIM1>,
+    hall: PhantomData<HALL>,
+}
+
+impl<HALL> SixStep<HALL>
+where
+    HALL: InternalTrigger<TIM1>,
+{
+    /// Takes the TIM1 PWM with its channel and complementary pins bound, the
+    /// commutation is triggered by the trigger output of the `HALL` timer
+    pub fn new(pwm: Pwm<TIM1>) -> Self {
+        let tim = unsafe { &*TIM1::ptr() };
+        // Preload CCxE, CCxNE and OCxM, COM event on trigger input
+        tim.cr2().modify(|_, w| w.ccpc().set_bit().ccus().set_bit());
+        #[cfg(not(any(feature = "stm32g0b1", feature = "stm32g0c1")))]
+        tim.smcr().modify(|_, w| unsafe { w.ts().bits(HALL::ITR) });
+        #[cfg(any(feature = "stm32g0b1", feature = "stm32g0c1"))]
+        tim.smcr().modify(|_, w| unsafe { w.ts1().bits(HALL::ITR) });
+        tim.ccmr1_output()
+            .modify(|_, w| w.oc1pe().set_bit().oc2pe().set_bit());
+        tim.ccmr2_output().modify(|_, w| w.oc3pe().set_bit());
+        tim.bdtr().modify(|_, w| w.moe().set_bit());
+        SixStep {
+            pwm,
+            hall: PhantomData,
+        }
+    }
+
+    /// Preloads the next step: `high` is switched with PWM, `low` is held low and
+    /// the third phase floats. Applied on the next commutation event.
+    pub fn set_step(&mut self, high: Phase, low: Phase) {
+        assert!(high != low);
+        let tim = unsafe { &*TIM1::ptr() };
+        let mode = |phase| {
+            if phase == high {
+                OutputMode::Pwm
+            } else if phase == low {
+                OutputMode::Low
+            } else {
+                OutputMode::Float
+            }
+        };
+        let (u, v, w) = (mode(Phase::U), mode(Phase::V), mode(Phase::W));
+        tim.ccmr1_output().modify(|_, wr| unsafe {
+            wr.oc1m()
+                .bits(u.compare_mode())
+                .oc2m()
+                .bits(v.compare_mode())
+        });
+        tim.ccmr2_output()
+            .modify(|_, wr| unsafe { wr.oc3m().bits(w.compare_mode()) });
+        tim.ccer().modify(|_, wr| {
+            wr.cc1e()
+                .bit(u.enabled())
+                .cc1ne()
+                .bit(u.enabled())
+                .cc2e()
+                .bit(v.enabled())
+                .cc2ne()
+                .bit(v.enabled())
+                .cc3e()
+                .bit(w.enabled())
+                .cc3ne()
+                .bit(w.enabled())
+        });
+    }
+
+    /// Preloads all phases floating, applied on the next commutation event
+    pub fn set_off(&mut self) {
+        Self::disable_phases();
+    }
+
+    /// Sets the PWM duty of the switched phase
+    pub fn set_duty(&mut self, duty: u16) {
+        let tim = unsafe { &*TIM1::ptr() };
+        for ch in 0..3 {
+            tim.ccr(ch).write(|w| unsafe { w.ccr().bits(duty) });
+        }
+    }
+
+    pub fn get_max_duty(&self) -> u16 {
+        let tim = unsafe { &*TIM1::ptr() };
+        tim.arr().read().arr().bits()
+    }
+
+    /// Applies the preloaded step immediately, e.g. to start the motor
+    pub fn commutate(&mut self) {
+        let tim = unsafe { &*TIM1::ptr() };
+        tim.egr().write(|w| w.comg().set_bit());
+    }
+
+    /// Starts listening for commutation events, to preload the following step
+    pub fn listen(&mut self) {
+        let tim = unsafe { &*TIM1::ptr() };
+        tim.dier().modify(|_, w| w.comie().set_bit());
+    }
+
+    /// Stops listening for commutation events
+    pub fn unlisten(&mut self) {
+        let tim = unsafe { &*TIM1::ptr() };
+        tim.dier().modify(|_, w| w.comie().clear_bit());
+    }
+
+    /// Clears interrupt flag
+    pub fn clear_irq(&mut self) {
+        let tim = unsafe { &*TIM1::ptr() };
+        tim.sr().modify(|_, w| w.comif().clear_bit());
+    }
+
+    /// Releases the PWM, outputs are disabled
+    pub fn release(self) -> Pwm<TIM1> {
+        Self::disable_phases();
+        let tim = unsafe { &*TIM1::ptr() };
+        tim.egr().write(|w| w.comg().set_bit());
+        tim.cr2()
+            .modify(|_, w| w.ccpc().clear_bit().ccus().clear_bit());
+        self.pwm
+    }
+
+    fn disable_phases() {
+        let tim = unsafe { &*TIM1::ptr() };
+        tim.ccer().modify(|_, w| {
+            w.cc1e()
+                .clear_bit()
+                .cc1ne()
+                .clear_bit()
+                .cc2e()
+                .clear_bit()
+                .cc2ne()
+                .clear_bit()
+                .cc3e()
+                .clear_bit()
+                .cc3ne()
+                .clear_bit()
+        });
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Eq)]
+enum OutputMode {
+    Pwm,
+    Low,
+    Float,
+}
+
+impl OutputMode {
+    fn compare_mode(self) -> u8 {
+        match self {
+            OutputMode::Pwm => OutputCompareMode::PwmMode1 as u8,
+            OutputMode::Low | OutputMode::Float => OutputCompareMode::ForceLow as u8,
+        }
+    }
+
+    fn enabled(self) -> bool {
+        self != OutputMode::Float
+    }
+}
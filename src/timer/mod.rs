@@ -7,12 +7,25 @@ use cortex_m::peripheral::syst::SystClkSource;
 use cortex_m::peripheral::SYST;
 use void::Void;
 
+pub mod capture;
 pub mod delay;
+pub mod hall;
+#[cfg(any(feature = "rtic-tim2", feature = "rtic-tim3", feature = "rtic-tim15"))]
+pub mod monotonic;
 pub mod opm;
 pub mod pins;
+pub mod pulse_train;
 pub mod pwm;
 pub mod qei;
 pub mod stopwatch;
+pub mod sync;
+#[cfg(any(
+    feature = "time-driver-tim2",
+    feature = "time-driver-tim3",
+    feature = "time-driver-tim15"
+))]
+pub mod time_driver;
+pub mod update;
 
 /// Hardware timers
 pub struct Timer<TIM> {
@@ -142,7 +155,9 @@ macro_rules! timers {
 
                 pub fn start(&mut self, timeout: MicroSecond) {
                     // Pause the counter. Also set URS so that when we set UG below, it will
-                    // generate an update event *without* triggering an interrupt.
+                    // generate an update event *without* triggering an interrupt. The
+                    // update source selected with `set_update_source` is restored after.
+                    let urs = self.tim.cr1().read().urs().bit();
                     self.tim.cr1().modify(|_, w| w.cen().clear_bit().urs().set_bit());
                     // reset counter
                     self.tim.cnt().reset();
@@ -161,7 +176,7 @@ macro_rules! timers {
                     // shadow registers.
                     self.tim.egr().write(|w| w.ug().set_bit());
 
-                    self.tim.cr1().modify(|_, w| w.cen().set_bit());
+                    self.tim.cr1().modify(|_, w| w.urs().bit(urs).cen().set_bit());
                 }
 
                 pub fn wait(&mut self) -> nb::Result<(), Void> {
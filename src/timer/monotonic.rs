@@ -0,0 +1,233 @@
+//! # RTIC Monotonic
+//!
+//! [`rtic_time`] monotonic timers on TIM2, TIM3 and TIM15, enabled with the
+//! `rtic-tim2`, `rtic-tim3` and `rtic-tim15` features. The counter is extended to
+//! 64 bits with a half-period compare on channel 2 and the overflow interrupt,
+//! channel 1 schedules the timer queue. The tick rate is fixed at compile time:
+//!
+//! ```ignore
+//! hal::tim3_monotonic!(Mono, 1_000_000);
+//!
+//! Mono::start(dp.TIM3, &mut rcc);
+//! Mono::delay(100.millis()).await;
+//! ```
+//!
+//! The feature also defines the timer interrupt handler.
+use crate::rcc::{Enable, Rcc, Reset};
+use crate::stm32;
+use core::ops::Deref;
+use core::sync::atomic::Ordering;
+use portable_atomic::AtomicU64;
+use rtic_time::half_period_counter::calculate_now;
+use rtic_time::timer_queue::{TimerQueue, TimerQueueBackend};
+
+#[doc(hidden)]
+pub use fugit;
+#[doc(hidden)]
+pub use rtic_time;
+
+macro_rules! monotonic {
+    ($($TIM:ident: (
+        $Backend:ident,
+        $queue:ident,
+        $overflow:ident,
+        $bits:ident,
+        $irq:ident,
+        $ccr1:ident $(($i1:literal))*,
+        $ccr2:ident $(($i2:literal))*
+    ),)+) => {
+        $(
+            static $overflow: AtomicU64 = AtomicU64::new(0);
+            static $queue: TimerQueue<$Backend> = TimerQueue::new();
+
+            #[doc = concat!("Monotonic backend on ", stringify!($TIM))]
+            pub struct $Backend;
+
+            impl $Backend {
+                /// Starts the monotonic, use the `start` method of the type created
+                /// by the monotonic macro instead
+                #[doc(hidden)]
+                pub fn _start(tim: stm32::$TIM, rcc: &mut Rcc, tick_rate_hz: u32) {
+                    let clk = rcc.clocks.apb_tim_clk.raw();
+                    assert!(
+                        clk % tick_rate_hz == 0,
+                        "tick rate must divide the timer clock"
+                    );
+                    let psc = clk / tick_rate_hz - 1;
+                    assert!(psc <= 0xffff);
+
+                    stm32::$TIM::enable(rcc);
+                    stm32::$TIM::reset(rcc);
+
+                    tim.psc().write(|w| unsafe { w.bits(psc) });
+                    tim.arr().write(|w| unsafe { w.bits($bits::MAX as u32) });
+                    // Load the prescaler without an update interrupt
+                    tim.cr1().modify(|_, w| w.urs().set_bit());
+                    tim.egr().write(|w| w.ug().set_bit());
+                    tim.sr().reset();
+
+                    // Half-period compare extends the counter together with overflows
+                    tim.$ccr2($($i2)*).write(|w| unsafe { w.bits(1 << ($bits::BITS - 1)) });
+                    tim.dier().modify(|_, w| w.uie().set_bit().cc2ie().set_bit());
+
+                    $overflow.store(0, Ordering::SeqCst);
+                    $queue.initialize($Backend);
+
+                    tim.cr1().modify(|_, w| w.cen().set_bit());
+
+                    unsafe {
+                        cortex_m::peripheral::NVIC::unmask(stm32::Interrupt::$irq);
+                    }
+                }
+
+                fn tim() -> &'static <stm32::$TIM as Deref>::Target {
+                    unsafe { &*stm32::$TIM::ptr() }
+                }
+            }
+
+            impl TimerQueueBackend for $Backend {
+                type Ticks = u64;
+
+                fn now() -> Self::Ticks {
+                    calculate_now(
+                        || $overflow.load(Ordering::Relaxed),
+                        || Self::tim().cnt().read().bits() as $bits,
+                    )
+                }
+
+                fn set_compare(instant: Self::Ticks) {
+                    let now = Self::now();
+                    // Instants beyond one counter period are reached through the
+                    // overflow interrupts, any compare value works meanwhile
+                    let value = if instant.wrapping_sub(now) <= $bits::MAX as u64 {
+                        instant as $bits
+                    } else {
+                        0
+                    };
+                    Self::tim()
+                        .$ccr1($($i1)*)
+                        .write(|w| unsafe { w.bits(value as u32) });
+                }
+
+                fn clear_compare_flag() {
+                    Self::tim().sr().modify(|_, w| w.cc1if().clear_bit());
+                }
+
+                fn pend_interrupt() {
+                    cortex_m::peripheral::NVIC::pend(stm32::Interrupt::$irq);
+                }
+
+                fn enable_timer() {
+                    Self::tim().dier().modify(|_, w| w.cc1ie().set_bit());
+                }
+
+                fn disable_timer() {
+                    Self::tim().dier().modify(|_, w| w.cc1ie().clear_bit());
+                }
+
+                fn on_interrupt() {
+                    let tim = Self::tim();
+                    // Half-period and overflow alternate, the counter is even after
+                    // an overflow and odd after the half-period
+                    if tim.sr().read().uif().bit_is_set() {
+                        tim.sr().modify(|_, w| w.uif().clear_bit());
+                        let prev = $overflow.fetch_add(1, Ordering::Relaxed);
+                        assert!(prev % 2 == 1, "monotonic overflow missed");
+                    }
+                    if tim.sr().read().cc2if().bit_is_set() {
+                        tim.sr().modify(|_, w| w.cc2if().clear_bit());
+                        let prev = $overflow.fetch_add(1, Ordering::Relaxed);
+                        assert!(prev % 2 == 0, "monotonic half-period missed");
+                    }
+                }
+
+                fn timer_queue() -> &'static TimerQueue<Self> {
+                    &$queue
+                }
+            }
+
+            #[no_mangle]
+            #[allow(non_snake_case)]
+            unsafe extern "C" fn $irq() {
+                $queue.on_monotonic_interrupt();
+            }
+        )+
+    };
+}
+
+/// Creates a monotonic type `$name` on TIM2 counting at `$tick_rate_hz`
+#[cfg(feature = "rtic-tim2")]
+#[macro_export]
+macro_rules! tim2_monotonic {
+    ($name:ident, $tick_rate_hz:expr) => {
+        $crate::__timer_monotonic!($name, $tick_rate_hz, TIM2, Tim2Backend);
+    };
+}
+
+/// Creates a monotonic type `$name` on TIM3 counting at `$tick_rate_hz`
+#[cfg(feature = "rtic-tim3")]
+#[macro_export]
+macro_rules! tim3_monotonic {
+    ($name:ident, $tick_rate_hz:expr) => {
+        $crate::__timer_monotonic!($name, $tick_rate_hz, TIM3, Tim3Backend);
+    };
+}
+
+/// Creates a monotonic type `$name` on TIM15 counting at `$tick_rate_hz`
+#[cfg(feature = "rtic-tim15")]
+#[macro_export]
+macro_rules! tim15_monotonic {
+    ($name:ident, $tick_rate_hz:expr) => {
+        $crate::__timer_monotonic!($name, $tick_rate_hz, TIM15, Tim15Backend);
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __timer_monotonic {
+    ($name:ident, $tick_rate_hz:expr, $TIM:ident, $Backend:ident) => {
+        pub struct $name;
+
+        impl $name {
+            /// Starts the monotonic, the timer clock must be a multiple of the tick rate
+            pub fn start(tim: $crate::stm32::$TIM, rcc: &mut $crate::rcc::Rcc) {
+                $crate::timer::monotonic::$Backend::_start(tim, rcc, $tick_rate_hz);
+            }
+        }
+
+        impl $crate::timer::monotonic::rtic_time::monotonic::TimerQueueBasedMonotonic for $name {
+            type Backend = $crate::timer::monotonic::$Backend;
+            type Instant = $crate::timer::monotonic::fugit::Instant<u64, 1, { $tick_rate_hz }>;
+            type Duration = $crate::timer::monotonic::fugit::Duration<u64, 1, { $tick_rate_hz }>;
+        }
+    };
+}
+
+#[cfg(all(feature = "rtic-tim2", feature = "stm32g0x1"))]
+monotonic! {
+    TIM2: (Tim2Backend, TIM2_QUEUE, TIM2_OVERFLOW, u32, TIM2, ccr1, ccr2),
+}
+
+#[cfg(all(
+    feature = "rtic-tim3",
+    not(any(feature = "stm32g0b1", feature = "stm32g0c1"))
+))]
+monotonic! {
+    TIM3: (Tim3Backend, TIM3_QUEUE, TIM3_OVERFLOW, u16, TIM3, ccr1, ccr2),
+}
+
+#[cfg(all(
+    feature = "rtic-tim3",
+    any(feature = "stm32g0b1", feature = "stm32g0c1")
+))]
+monotonic! {
+    TIM3: (Tim3Backend, TIM3_QUEUE, TIM3_OVERFLOW, u16, TIM3_TIM4, ccr1, ccr2),
+}
+
+#[cfg(all(
+    feature = "rtic-tim15",
+    any(feature = "stm32g070", feature = "stm32g071", feature = "stm32g081")
+))]
+monotonic! {
+    TIM15: (Tim15Backend, TIM15_QUEUE, TIM15_OVERFLOW, u16, TIM15, ccr(0), ccr(1)),
+}
@@ -1,3 +1,4 @@
+use crate::analog::adc::AnalogWatchdog;
 use crate::gpio::*;
 use crate::gpio::{AltFunction, DefaultMode};
 use crate::stm32::*;
@@ -10,6 +11,54 @@ pub trait TimerPin<TIM> {
     fn release(self) -> Self;
 }
 
+/// Complementary output CHxN of an advanced-control timer channel
+pub trait ComplementaryPin<TIM> {
+    type Channel;
+
+    fn setup(&self);
+    fn release(self) -> Self;
+}
+
+/// Break input of an advanced-control timer
+pub trait BreakPin<TIM, BK> {
+    fn setup(&self);
+    fn release(self) -> Self;
+}
+
+/// BKIN break input
+pub struct Break;
+/// BKIN2 break input
+pub struct Break2;
+
+/// Internal signal clearing the OCxREF outputs of a timer while high
+pub trait OcrefClearSource<TIM> {
+    fn setup(&self);
+}
+
+/// ETRF, the filtered external trigger input, as OCREF_CLR source
+pub struct Etr;
+
+impl OcrefClearSource<TIM1> for Etr {
+    fn setup(&self) {
+        let tim = unsafe { &*TIM1::ptr() };
+        tim.smcr().modify(|_, w| w.occs().set_bit());
+    }
+}
+
+/// ADC analog watchdog output, routed to ETR (ETRSEL), as OCREF_CLR source
+impl OcrefClearSource<TIM1> for &AnalogWatchdog {
+    fn setup(&self) {
+        let etrsel = match self {
+            AnalogWatchdog::Awd1 => 0b0011,
+            AnalogWatchdog::Awd2 => 0b0100,
+            AnalogWatchdog::Awd3 => 0b0101,
+        };
+        let tim = unsafe { &*TIM1::ptr() };
+        tim.af1().modify(|_, w| unsafe { w.etrsel().bits(etrsel) });
+        tim.smcr().modify(|_, w| w.occs().set_bit());
+    }
+}
+
 pub struct TriggerPin<TIM, PIN: TimerPin<TIM>> {
     pin: PIN,
     tim: PhantomData<TIM>,
@@ -60,6 +109,57 @@ macro_rules! timer_pins {
     };
 }
 
+macro_rules! complementary_pin_impl {
+    ($TIMX:ident, $ch:ty, $pin:tt, $af_mode:expr, [ $(($mode:ty, $release:ident),)+]) => {
+        $(
+            impl ComplementaryPin<$TIMX> for $pin<$mode> {
+                type Channel = $ch;
+
+                fn setup(&self) {
+                    self.set_alt_mode($af_mode);
+                }
+
+                fn release(self) -> Self {
+                    self.$release()
+                }
+            }
+        )+
+    };
+}
+
+// CHxN pins are not `TimerPin`s: they only output a signal once the complementary
+// output is enabled, see `Pwm::bind_complementary_pin`
+macro_rules! complementary_pins {
+    ($TIMX:ident, [ $(($ch:ty, $pin:tt, $af_mode:expr),)+ ]) => {
+        $(
+            complementary_pin_impl!($TIMX, $ch, $pin, $af_mode, [
+                (Analog, into_analog),
+                (Output<OpenDrain>, into_open_drain_output),
+                (Output<PushPull>, into_push_pull_output),
+                (Input<Floating>, into_floating_input),
+                (Input<PullUp>, into_pull_up_input),
+                (Input<PullDown>, into_pull_down_input),
+            ]);
+        )+
+    };
+}
+
+macro_rules! break_pins {
+    ($TIMX:ident, [ $(($bk:ty, $pin:tt, $af_mode:expr),)+ ]) => {
+        $(
+            impl BreakPin<$TIMX, $bk> for $pin<DefaultMode> {
+                fn setup(&self) {
+                    self.set_alt_mode($af_mode);
+                }
+
+                fn release(self) -> Self {
+                    self.into_analog()
+                }
+            }
+        )+
+    };
+}
+
 macro_rules! trigger_pins {
     ($TIMX:ident, [ $(($pin:ty, $ccp:ident $(,$icf:ident)*),)+ ]) => {
         $(
@@ -149,7 +249,7 @@ timer_pins!(
 );
 
 // Inverted pins
-timer_pins!(
+complementary_pins!(
     TIM1,
     [
         (Channel1, PA7, AltFunction::AF2),
@@ -226,7 +326,7 @@ timer_pins!(
 
 // Inverted pins
 #[cfg(any(feature = "stm32g070", feature = "stm32g071", feature = "stm32g081"))]
-timer_pins!(
+complementary_pins!(
     TIM15,
     [
         (Channel1, PA1, AltFunction::AF5),
@@ -245,7 +345,7 @@ timer_pins!(
 );
 
 // Inverted pins
-timer_pins!(TIM16, [(Channel1, PB6, AltFunction::AF2),]);
+complementary_pins!(TIM16, [(Channel1, PB6, AltFunction::AF2),]);
 
 timer_pins!(
     TIM17,
@@ -257,4 +357,34 @@ timer_pins!(
 );
 
 //  Inverted pins
-timer_pins!(TIM17, [(Channel1, PB7, AltFunction::AF2),]);
+complementary_pins!(TIM17, [(Channel1, PB7, AltFunction::AF2),]);
+
+break_pins!(
+    TIM1,
+    [
+        (Break, PA6, AltFunction::AF2),
+        (Break, PB12, AltFunction::AF2),
+        (Break, PC13, AltFunction::AF2),
+        (Break2, PA11, AltFunction::AF5),
+        (Break2, PC14, AltFunction::AF2),
+    ]
+);
+
+#[cfg(any(feature = "stm32g070", feature = "stm32g071", feature = "stm32g081"))]
+break_pins!(
+    TIM15,
+    [
+        (Break, PB12, AltFunction::AF5),
+        (Break, PC15, AltFunction::AF2),
+    ]
+);
+
+break_pins!(TIM16, [(Break, PB5, AltFunction::AF2),]);
+
+break_pins!(
+    TIM17,
+    [
+        (Break, PA10, AltFunction::AF5),
+        (Break, PB4, AltFunction::AF5),
+    ]
+);
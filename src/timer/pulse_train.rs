@@ -0,0 +1,260 @@
+//! # Pulse Train
+//!
+//! Emits exactly N pulses on channel 1 of a timer with a repetition counter (TIM1,
+//! TIM15, TIM16, TIM17), e.g. the step input of a stepper motor driver. A [`Move`]
+//! is split into chunks of pulses at the same frequency: the counter runs with
+//! preloaded ARR, CCR1 and RCR, the update interrupt at the end of each chunk
+//! preloads the one after next, and one-pulse mode stops the counter after the
+//! last chunk. Call [`PulseTrain::on_update`] from the timer update interrupt.
+//!
+//! A chunk lasts at least one pulse period, which must be longer than the interrupt
+//! latency. A queued move starts from the interrupt ending the current one.
+use crate::rcc::*;
+use crate::stm32::*;
+use crate::time::Hertz;
+use crate::timer::pins::TimerPin;
+use crate::timer::*;
+use core::marker::PhantomData;
+
+/// Number of frequency steps of a ramp
+const RAMP_STEPS: u32 = 16;
+
+/// Pulses at a constant frequency, with an optional linear acceleration and
+/// deceleration ramp
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Move {
+    pulses: u32,
+    freq: Hertz,
+    start_freq: Hertz,
+    ramp: u32,
+    scheduled: u32,
+}
+
+impl Move {
+    /// Creates a move of `pulses` pulses at `freq`
+    pub fn new(pulses: u32, freq: Hertz) -> Self {
+        assert!(pulses > 0 && freq.raw() > 0);
+        Move {
+            pulses,
+            freq,
+            start_freq: freq,
+            ramp: 0,
+            scheduled: 0,
+        }
+    }
+
+    /// Ramps the frequency linearly from `start_freq` to the move frequency over
+    /// the first `pulses` pulses, and back over the last ones. Short moves ramp
+    /// over half of their pulses each way.
+    pub fn ramp(mut self, start_freq: Hertz, pulses: u32) -> Self {
+        assert!(start_freq.raw() > 0);
+        self.start_freq = start_freq;
+        self.ramp = pulses;
+        self
+    }
+
+    /// Returns the number of pulses of the move
+    pub fn pulses(&self) -> u32 {
+        self.pulses
+    }
+
+    fn min_freq(&self) -> Hertz {
+        if self.ramp > 0 {
+            self.freq.min(self.start_freq)
+        } else {
+            self.freq
+        }
+    }
+
+    /// Frequency `pulse` pulses into a ramp of `ramp` pulses
+    fn ramp_freq(&self, pulse: u32, ramp: u32) -> Hertz {
+        let start = self.start_freq.raw() as i64;
+        let end = self.freq.raw() as i64;
+        let freq = start + (end - start) * pulse as i64 / ramp as i64;
+        Hertz::from_raw(freq as u32)
+    }
+
+    /// Returns the pulse count and frequency of the next chunk
+    fn next_chunk(&mut self, max_count: u32) -> Option<(u32, Hertz)> {
+        let remaining = self.pulses - self.scheduled;
+        if remaining == 0 {
+            return None;
+        }
+        let ramp = self.ramp.min(self.pulses / 2);
+        let step = (ramp / RAMP_STEPS).clamp(1, max_count);
+        let pulse = self.scheduled;
+
+        let chunk = if pulse < ramp {
+            let count = step.min(ramp - pulse);
+            (count, self.ramp_freq(pulse + count / 2, ramp))
+        } else if pulse < self.pulses - ramp {
+            let count = (self.pulses - ramp - pulse).min(max_count);
+            (count, self.freq)
+        } else {
+            let count = step.min(remaining);
+            (count, self.ramp_freq(remaining - count / 2 - 1, ramp))
+        };
+        self.scheduled += chunk.0;
+        Some(chunk)
+    }
+}
+
+pub struct PulseTrain<TIM> {
+    tim: PhantomData<TIM>,
+    clk: Hertz,
+    /// Counter clock of the running move
+    tick: Hertz,
+    current: Option<Move>,
+    next: Option<Move>,
+}
+
+pub trait PulseTrainExt: Sized {
+    /// Configures the timer to emit pulse trains on the channel 1 `pin`
+    fn pulse_train<PIN>(self, pin: PIN, rcc: &mut Rcc) -> PulseTrain<Self>
+    where
+        PIN: TimerPin<Self, Channel = Channel1>;
+}
+
+macro_rules! pulse_train {
+    ($($TIMX:ident: ($timX:ident, $rep:ty),)+) => {
+        $(
+            impl PulseTrainExt for $TIMX {
+                fn pulse_train<PIN>(self, pin: PIN, rcc: &mut Rcc) -> PulseTrain<Self>
+                where
+                    PIN: TimerPin<Self, Channel = Channel1>,
+                {
+                    PulseTrain::$timX(self, pin, rcc)
+                }
+            }
+
+            impl PulseTrain<$TIMX> {
+                fn $timX<PIN>(tim: $TIMX, pin: PIN, rcc: &mut Rcc) -> Self
+                where
+                    PIN: TimerPin<$TIMX, Channel = Channel1>,
+                {
+                    $TIMX::enable(rcc);
+                    $TIMX::reset(rcc);
+
+                    // PWM mode 2 keeps the output low while the counter is stopped
+                    tim.ccmr1_output().write(|w| unsafe { w.oc1m().bits(7).oc1pe().set_bit() });
+                    tim.ccer().write(|w| w.cc1e().set_bit());
+                    tim.bdtr().modify(|_, w| w.moe().set_bit());
+                    tim.cr1().write(|w| w.urs().set_bit().arpe().set_bit());
+                    tim.dier().write(|w| w.uie().set_bit());
+                    pin.setup();
+
+                    PulseTrain {
+                        tim: PhantomData,
+                        clk: rcc.clocks.apb_tim_clk,
+                        tick: rcc.clocks.apb_tim_clk,
+                        current: None,
+                        next: None,
+                    }
+                }
+
+                /// Returns true while a move is running
+                pub fn is_busy(&self) -> bool {
+                    self.current.is_some()
+                }
+
+                /// Starts the move, or queues it behind the running one. Returns the
+                /// move back if one is already queued.
+                pub fn queue(&mut self, mv: Move) -> Result<(), Move> {
+                    if self.current.is_none() {
+                        self.start(mv);
+                        Ok(())
+                    } else if self.next.is_none() {
+                        self.next = Some(mv);
+                        Ok(())
+                    } else {
+                        Err(mv)
+                    }
+                }
+
+                /// Stops the running move at the end of the running chunk and drops
+                /// the queued one, `on_update` then reports the move as completed
+                pub fn stop(&mut self) {
+                    let tim = unsafe { &*$TIMX::ptr() };
+                    tim.cr1().modify(|_, w| w.opm().set_bit());
+                    self.next = None;
+                }
+
+                /// Handles the update interrupt, returns true when a move completed
+                pub fn on_update(&mut self) -> bool {
+                    let tim = unsafe { &*$TIMX::ptr() };
+                    if tim.sr().read().uif().bit_is_clear() {
+                        return false;
+                    }
+                    tim.sr().modify(|_, w| w.uif().clear_bit());
+
+                    if tim.cr1().read().cen().bit_is_clear() {
+                        // One-pulse mode stopped the counter after the last chunk
+                        self.current = None;
+                        if let Some(mv) = self.next.take() {
+                            self.start(mv);
+                        }
+                        return true;
+                    }
+
+                    // The preloaded chunk is running, preload the following one
+                    if let Some(mv) = self.current.as_mut() {
+                        match mv.next_chunk(<$rep>::MAX as u32 + 1) {
+                            Some((count, freq)) => Self::preload(self.tick, count, freq),
+                            None => {
+                                tim.cr1().modify(|_, w| w.opm().set_bit());
+                            }
+                        }
+                    }
+                    false
+                }
+
+                fn start(&mut self, mut mv: Move) {
+                    let tim = unsafe { &*$TIMX::ptr() };
+                    let max_count = <$rep>::MAX as u32 + 1;
+
+                    let cycles = self.clk.raw() / mv.min_freq().raw();
+                    let psc = cycles.saturating_sub(1) / 0x1_0000;
+                    assert!(psc <= 0xffff);
+                    tim.psc().write(|w| unsafe { w.psc().bits(psc as u16) });
+                    let tick = Hertz::from_raw(self.clk.raw() / (psc + 1));
+
+                    let (count, freq) = mv.next_chunk(max_count).unwrap();
+                    Self::preload(tick, count, freq);
+                    tim.cr1().modify(|_, w| w.opm().clear_bit());
+                    tim.egr().write(|w| w.ug().set_bit());
+                    match mv.next_chunk(max_count) {
+                        Some((count, freq)) => Self::preload(tick, count, freq),
+                        None => {
+                            tim.cr1().modify(|_, w| w.opm().set_bit());
+                        }
+                    }
+
+                    self.tick = tick;
+                    self.current = Some(mv);
+                    tim.cr1().modify(|_, w| w.cen().set_bit());
+                }
+
+                /// Writes the preload registers of a chunk, loaded on the next update
+                fn preload(tick: Hertz, count: u32, freq: Hertz) {
+                    let tim = unsafe { &*$TIMX::ptr() };
+                    let arr = (tick.raw() / freq.raw()).clamp(2, 0x1_0000) - 1;
+                    tim.arr().write(|w| unsafe { w.bits(arr) });
+                    tim.ccr(0).write(|w| unsafe { w.ccr().bits(((arr + 1) / 2) as u16) });
+                    tim.rcr().write(|w| unsafe { w.rep().bits((count - 1) as $rep) });
+                }
+            }
+        )+
+    };
+}
+
+pulse_train! {
+    TIM1: (tim1, u16),
+    TIM16: (tim16, u8),
+    TIM17: (tim17, u8),
+}
+
+#[cfg(any(feature = "stm32g070", feature = "stm32g071", feature = "stm32g081"))]
+pulse_train! {
+    TIM15: (tim15, u8),
+}
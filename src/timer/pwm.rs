@@ -1,10 +1,12 @@
 //! # Pulse Width Modulation
 use core::marker::PhantomData;
 
+use crate::dma;
+use crate::dmamux::DmaMuxIndex;
 use crate::rcc::*;
 use crate::stm32::*;
-use crate::time::Hertz;
-use crate::timer::pins::TimerPin;
+use crate::time::{Hertz, NanoSecond};
+use crate::timer::pins::{Break, Break2, BreakPin, ComplementaryPin, OcrefClearSource, TimerPin};
 use crate::timer::*;
 use embedded_hal::pwm::{ErrorKind, ErrorType, SetDutyCycle};
 
@@ -27,9 +29,56 @@ pub enum OutputCompareMode {
     AsyncMode2 = 15,
 }
 
+/// Output or break input active level
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Polarity {
+    ActiveHigh,
+    ActiveLow,
+}
+
+/// PWM configuration error
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Error {
+    /// The dead-time is above 1008 cycles of the timer clock divided by 4
+    DeadTimeTooLong,
+}
+
+/// Write protection of the break and dead-time configuration, see LOCK in the
+/// reference manual. Once set, the level can only be changed by a reset.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum LockLevel {
+    Off = 0,
+    /// Locks dead-time, break and output idle state configuration
+    Level1 = 1,
+    /// Level 1 plus output polarities and off-state selection
+    Level2 = 2,
+    /// Level 2 plus output compare modes
+    Level3 = 3,
+}
+
+/// Counter mode
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum CounterMode {
+    /// Edge-aligned, counting up
+    Up,
+    /// Edge-aligned, counting down
+    Down,
+    /// Center-aligned, compare flags set when counting down
+    CenterAligned1,
+    /// Center-aligned, compare flags set when counting up
+    CenterAligned2,
+    /// Center-aligned, compare flags set when counting up and down
+    CenterAligned3,
+}
+
 pub struct Pwm<TIM> {
     clk: Hertz,
     tim: TIM,
+    center_aligned: bool,
 }
 
 pub struct PwmPin<TIM, CH> {
@@ -37,6 +86,12 @@ pub struct PwmPin<TIM, CH> {
     channel: PhantomData<CH>,
 }
 
+/// Complementary output CHxN of an advanced-control timer channel
+pub struct ComplementaryPwmPin<TIM, CH> {
+    tim: PhantomData<TIM>,
+    channel: PhantomData<CH>,
+}
+
 enum ClockSource {
     ApbTim,
     #[allow(dead_code)]
@@ -68,6 +123,28 @@ impl<TIM> Pwm<TIM> {
             channel: PhantomData,
         }
     }
+
+    pub fn bind_complementary_pin<PIN>(&self, pin: PIN) -> ComplementaryPwmPin<TIM, PIN::Channel>
+    where
+        PIN: ComplementaryPin<TIM>,
+    {
+        pin.setup();
+        ComplementaryPwmPin {
+            tim: PhantomData,
+            channel: PhantomData,
+        }
+    }
+}
+
+/// Encodes a dead-time in tDTS cycles into DTG, rounding up
+fn dead_time_bits(ticks: u32) -> Option<u8> {
+    match ticks {
+        0..=127 => Some(ticks as u8),
+        128..=254 => Some(0b1000_0000 | (ticks.div_ceil(2) - 64) as u8),
+        255..=504 => Some(0b1100_0000 | (ticks.div_ceil(8) - 32) as u8),
+        505..=1008 => Some(0b1110_0000 | (ticks.div_ceil(16) - 32) as u8),
+        _ => None,
+    }
 }
 
 macro_rules! pwm {
@@ -97,6 +174,7 @@ macro_rules! pwm {
                 let mut pwm = Pwm::<$TIMX> {
                     clk,
                     tim,
+                    center_aligned: false,
                 };
                 pwm.set_freq(freq);
                 pwm
@@ -108,16 +186,22 @@ macro_rules! pwm {
                 /// frequency, call freq.
                 pub fn set_freq(&mut self, freq: Hertz) {
                     let ratio = self.clk / freq;
+                    // A center-aligned period counts up to ARR and back down
+                    let (ratio, offset) = if self.center_aligned {
+                        (ratio / 2, 0)
+                    } else {
+                        (ratio, 1)
+                    };
                     let psc = (ratio - 1) / 0xffff;
 
                     unsafe {
-                        let arr = ratio / (psc + 1) - 1;
+                        let arr = ratio / (psc + 1) - offset;
                         self.tim.psc().write(|w| w.psc().bits(psc as u16));
                         self.tim.arr().write(|w| w.$arr().bits((arr as u16).into()));
                         $(
                             self.tim.arr().modify(|_, w| w.$arr_h().bits((arr >> 16) as u16));
                         )*
-                        self.tim.cr1().write(|w| w.cen().set_bit());
+                        self.tim.cr1().modify(|_, w| w.cen().set_bit());
                     }
                 }
                 /// Starts listening
@@ -141,9 +225,11 @@ macro_rules! pwm {
 
                 /// Returns the currently configured frequency
                 pub fn freq(&self) -> Hertz {
+                    let arr = self.tim.arr().read().bits();
+                    let period = if self.center_aligned { 2 * arr } else { arr + 1 };
                     Hertz::from_raw(self.clk.raw()
                         / (self.tim.psc().read().bits() + 1)
-                        / (self.tim.arr().read().bits() + 1))
+                        / period)
                 }
             }
         )+
@@ -194,6 +280,15 @@ macro_rules! pwm_hal {
 
                 }
 
+                /// Buffers the compare register (OCxPE), a new duty then only takes
+                /// effect on the next update event. Enabled by `enable`.
+                pub fn set_preload(&mut self, enable: bool) {
+                    unsafe {
+                        let tim = &*$TIMX::ptr();
+                        tim.$ccmrx_output().modify(|_, w| w.$ocxpe().bit(enable));
+                    }
+                }
+
                 pub fn set_duty(&mut self, duty: u32) {
                     unsafe { (*$TIMX::ptr()).$ccrx().write(|w| w.bits(duty)); }
                 }
@@ -220,7 +315,7 @@ macro_rules! pwm_hal {
 macro_rules! pwm_advanced_hal {
     ($($TIMX:ident: (
         $CH:ty,
-        $ccxe:ident $(: $ccxne:ident)*,
+        $ccxe:ident,
         $ccmrx_output:ident,
         $ocxpe:ident,
         $ocxm:ident,
@@ -241,9 +336,6 @@ macro_rules! pwm_advanced_hal {
                         let tim = &*$TIMX::ptr();
                         tim.$ccmrx_output().modify(|_, w| w.$ocxpe().set_bit().$ocxm().bits(6));
                         tim.ccer().modify(|_, w| w.$ccxe().set_bit());
-                        $(
-                            tim.ccer().modify(|_, w| w.$ccxne().bit(true));
-                        )*
                         $(
                             tim.bdtr().modify(|_, w| w.$moe().set_bit());
                         )*
@@ -258,6 +350,15 @@ macro_rules! pwm_advanced_hal {
                     unsafe { (*$TIMX::ptr()).arr().read().arr().bits() }
                 }
 
+                /// Buffers the compare register (OCxPE), a new duty then only takes
+                /// effect on the next update event. Enabled by `enable`.
+                pub fn set_preload(&mut self, enable: bool) {
+                    unsafe {
+                        let tim = &*$TIMX::ptr();
+                        tim.$ccmrx_output().modify(|_, w| w.$ocxpe().bit(enable));
+                    }
+                }
+
                 pub fn set_duty(&mut self, duty: u16) {
                     unsafe { (*$TIMX::ptr()).$ccrx(<$CH>::N).write(|w| w.$ccrx().bits(duty)); }
                 }
@@ -290,19 +391,416 @@ macro_rules! pwm_advanced_hal {
     };
 }
 
+macro_rules! pwm_counter_mode {
+    ($($TIMX:ident,)+) => {
+        $(
+            impl Pwm<$TIMX> {
+                /// Selects the counter mode and recomputes the prescaler and the
+                /// auto-reload value to keep the PWM frequency
+                pub fn set_counter_mode(&mut self, mode: CounterMode) {
+                    let freq = self.freq();
+                    let (dir, cms) = match mode {
+                        CounterMode::Up => (false, 0b00),
+                        CounterMode::Down => (true, 0b00),
+                        CounterMode::CenterAligned1 => (false, 0b01),
+                        CounterMode::CenterAligned2 => (false, 0b10),
+                        CounterMode::CenterAligned3 => (false, 0b11),
+                    };
+                    // The counting mode can only be changed with the counter disabled
+                    self.tim.cr1().modify(|_, w| w.cen().clear_bit());
+                    self.tim.cr1().modify(|_, w| unsafe { w.dir().bit(dir).cms().bits(cms) });
+                    self.center_aligned = cms != 0b00;
+                    self.set_freq(freq);
+                }
+            }
+        )+
+    };
+}
+
+macro_rules! pwm_combined {
+    ($($TIMX:ident: ($CH:ty, $ccmr_output:ident, $ocxm:ident, $ocym:ident),)+) => {
+        $(
+            impl PwmPin<$TIMX, $CH> {
+                /// Asymmetric PWM, center-aligned counter modes only. The output is
+                /// set when counting up past `up` and reset when counting down past
+                /// `down`, shifting the pulse within the period. Uses the compare
+                /// register of the next channel, which can't be used for PWM
+                /// meanwhile. Call after `enable`.
+                ///
+                /// Panics if the counter is edge-aligned, see `Pwm::set_counter_mode`.
+                pub fn set_asymmetric_duty(&mut self, up: u16, down: u16) {
+                    let tim = unsafe { &*$TIMX::ptr() };
+                    assert!(tim.cr1().read().cms().bits() != 0b00);
+                    tim.ccr(<$CH>::N).write(|w| unsafe { w.ccr().bits(up) });
+                    tim.ccr(<$CH>::N + 1).write(|w| unsafe { w.ccr().bits(down) });
+                    // PWM mode 2 polarity: active while the counter is above the
+                    // compare value of its counting direction
+                    self.set_compare_mode(OutputCompareMode::AsyncMode2);
+                }
+
+                /// Phase-shifted PWM, edge-aligned up-counting mode only. The output
+                /// is active while the counter is between `start` and `end`, combining
+                /// this channel with the next one, which can't be used for PWM
+                /// meanwhile. Call after `enable`.
+                pub fn set_phase_shifted_duty(&mut self, start: u16, end: u16) {
+                    assert!(start <= end);
+                    let tim = unsafe { &*$TIMX::ptr() };
+                    tim.ccr(<$CH>::N).write(|w| unsafe { w.ccr().bits(start) });
+                    tim.ccr(<$CH>::N + 1).write(|w| unsafe { w.ccr().bits(end) });
+                    // OCxREFC = OCxREF (PWM mode 2) AND OCyREF (PWM mode 1)
+                    tim.$ccmr_output().modify(|_, w| unsafe {
+                        w.$ocxm()
+                            .bits(OutputCompareMode::CombinedMode2 as u8)
+                            .$ocym()
+                            .bits(OutputCompareMode::PwmMode1 as u8)
+                    });
+                }
+            }
+        )+
+    };
+}
+
+macro_rules! pwm_dma {
+    ($($TIMX:ident: ($dmamux:ident, $channels:literal),)+) => {
+        $(
+            impl Pwm<$TIMX> {
+                /// Configures DMA bursts transferring `channels` compare values, starting
+                /// with `first_channel` (0-based), on every update DMA request. Point the
+                /// DMA peripheral address to `dma_burst_address` and the memory address
+                /// to a buffer holding `channels` values per period.
+                pub fn set_dma_burst(&mut self, first_channel: u8, channels: u8) {
+                    assert!(channels > 0 && first_channel + channels <= $channels);
+                    // CCR1 is at offset 0x34, DBA counts 32-bit words from CR1
+                    let dba = 0x34 / 4 + first_channel;
+                    self.tim.dcr().write(|w| unsafe { w.dba().bits(dba).dbl().bits(channels - 1) });
+                }
+
+                /// Returns the DMA burst register address, DMA peripheral address for bursts
+                pub fn dma_burst_address(&self) -> u32 {
+                    self.tim.dmar().as_ptr() as u32
+                }
+            }
+
+            impl dma::Target for Pwm<$TIMX> {
+                fn dmamux(&self) -> DmaMuxIndex {
+                    DmaMuxIndex::$dmamux
+                }
+
+                fn enable_dma(&mut self) {
+                    self.tim.dier().modify(|_, w| w.ude().set_bit());
+                }
+
+                fn disable_dma(&mut self) {
+                    self.tim.dier().modify(|_, w| w.ude().clear_bit());
+                }
+            }
+        )+
+    };
+}
+
+macro_rules! pwm_pin_dma {
+    ($($TIMX:ident: ($CH:ty, $ccxde:ident, $dmamux:ident),)+) => {
+        $(
+            impl PwmPin<$TIMX, $CH> {
+                /// Returns the compare register address, DMA peripheral address to
+                /// stream duty values to this channel
+                pub fn duty_address(&self) -> u32 {
+                    // CCRx registers start at offset 0x34 on all timers
+                    $TIMX::ptr() as u32 + 0x34 + 4 * <$CH>::N as u32
+                }
+            }
+
+            /// Capture/compare DMA request of the channel, one duty value is
+            /// transferred per compare match
+            impl dma::Target for PwmPin<$TIMX, $CH> {
+                fn dmamux(&self) -> DmaMuxIndex {
+                    DmaMuxIndex::$dmamux
+                }
+
+                fn enable_dma(&mut self) {
+                    let tim = unsafe { &*$TIMX::ptr() };
+                    tim.dier().modify(|_, w| w.$ccxde().set_bit());
+                }
+
+                fn disable_dma(&mut self) {
+                    let tim = unsafe { &*$TIMX::ptr() };
+                    tim.dier().modify(|_, w| w.$ccxde().clear_bit());
+                }
+            }
+        )+
+    };
+}
+
+pwm_dma! {
+    TIM1: (TIM1_UP, 4),
+    TIM3: (TIM3_UP, 4),
+    TIM16: (TIM16_UP, 1),
+    TIM17: (TIM17_UP, 1),
+}
+
+#[cfg(feature = "stm32g0x1")]
+pwm_dma! {
+    TIM2: (TIM2_UP, 4),
+}
+
+#[cfg(any(feature = "stm32g070", feature = "stm32g071", feature = "stm32g081"))]
+pwm_dma! {
+    TIM15: (TIM15_UP, 2),
+}
+
+pwm_pin_dma! {
+    TIM1: (Channel1, cc1de, TIM1_CH1),
+    TIM1: (Channel2, cc2de, TIM1_CH2),
+    TIM1: (Channel3, cc3de, TIM1_CH3),
+    TIM1: (Channel4, cc4de, TIM1_CH4),
+    TIM16: (Channel1, cc1de, TIM16_CH1),
+    TIM17: (Channel1, cc1de, TIM17_CH1),
+}
+
+#[cfg(any(feature = "stm32g0x1", feature = "stm32g070"))]
+pwm_pin_dma! {
+    TIM3: (Channel1, cc1de, TIM3_CH1),
+    TIM3: (Channel2, cc2de, TIM3_CH2),
+    TIM3: (Channel3, cc3de, TIM3_CH3),
+    TIM3: (Channel4, cc4de, TIM3_CH4),
+}
+
+#[cfg(feature = "stm32g0x1")]
+pwm_pin_dma! {
+    TIM2: (Channel1, cc1de, TIM2_CH1),
+    TIM2: (Channel2, cc2de, TIM2_CH2),
+    TIM2: (Channel3, cc3de, TIM2_CH3),
+    TIM2: (Channel4, cc4de, TIM2_CH4),
+}
+
+#[cfg(any(feature = "stm32g070", feature = "stm32g071", feature = "stm32g081"))]
+pwm_pin_dma! {
+    TIM15: (Channel1, cc1de, TIM15_CH1),
+}
+
+pwm_counter_mode! {
+    TIM1,
+    TIM3,
+}
+
+#[cfg(feature = "stm32g0x1")]
+pwm_counter_mode! {
+    TIM2,
+}
+
+pwm_combined! {
+    TIM1: (Channel1, ccmr1_output, oc1m, oc2m),
+    TIM1: (Channel3, ccmr2_output, oc3m, oc4m),
+}
+
+macro_rules! pwm_ocref_clear {
+    ($TIMX:ident: ($($CH:ty: ($ccmr_output:ident, $ocxce:ident),)+)) => {
+        impl Pwm<$TIMX> {
+            /// Selects the signal clearing the outputs enabled with
+            /// `PwmPin::set_ocref_clear`, e.g. a comparator for peak current
+            /// mode control. The output stays low until the next update event.
+            pub fn set_ocref_clear_source<SRC>(&mut self, source: SRC)
+            where
+                SRC: OcrefClearSource<$TIMX>,
+            {
+                source.setup();
+            }
+        }
+
+        $(
+            impl PwmPin<$TIMX, $CH> {
+                /// Clears the output while the OCREF_CLR input is high (OCxCE)
+                pub fn set_ocref_clear(&mut self, enable: bool) {
+                    let tim = unsafe { &*$TIMX::ptr() };
+                    tim.$ccmr_output().modify(|_, w| w.$ocxce().bit(enable));
+                }
+            }
+        )+
+    };
+}
+
+pwm_ocref_clear! {
+    TIM1: (
+        Channel1: (ccmr1_output, oc1ce),
+        Channel2: (ccmr1_output, oc2ce),
+        Channel3: (ccmr2_output, oc3ce),
+        Channel4: (ccmr2_output, oc4ce),
+    )
+}
+
+macro_rules! pwm_complementary_hal {
+    ($($TIMX:ident: ($CH:ty, $ccxne:ident, $ccxnp:ident),)+) => {
+        $(
+            impl ComplementaryPwmPin<$TIMX, $CH> {
+                pub fn enable(&mut self) {
+                    let tim = unsafe { &*$TIMX::ptr() };
+                    tim.ccer().modify(|_, w| w.$ccxne().set_bit());
+                    tim.bdtr().modify(|_, w| w.moe().set_bit());
+                }
+
+                pub fn disable(&mut self) {
+                    let tim = unsafe { &*$TIMX::ptr() };
+                    tim.ccer().modify(|_, w| w.$ccxne().clear_bit());
+                }
+
+                pub fn set_polarity(&mut self, polarity: Polarity) {
+                    let tim = unsafe { &*$TIMX::ptr() };
+                    tim.ccer().modify(|_, w| w.$ccxnp().bit(polarity == Polarity::ActiveLow));
+                }
+            }
+        )+
+    };
+}
+
+macro_rules! pwm_break {
+    ($($TIMX:ident: ($($Break2:ident)*),)+) => {
+        $(
+            impl Pwm<$TIMX> {
+                /// Inserts a dead-time between a channel output and its complementary
+                /// output. Dead-times above 1008 timer clock cycles lower the dead-time
+                /// and sampling clock of the filters (CKD).
+                pub fn set_dead_time(&mut self, dead_time: NanoSecond) -> Result<(), Error> {
+                    for ckd in 0..3 {
+                        let clk = (self.clk.raw() >> ckd) as u64;
+                        let ticks = (dead_time.ticks() as u64 * clk).div_ceil(1_000_000_000);
+                        if let Some(dtg) = dead_time_bits(ticks as u32) {
+                            self.tim.cr1().modify(|_, w| unsafe { w.ckd().bits(ckd) });
+                            self.tim.bdtr().modify(|_, w| unsafe { w.dtg().bits(dtg) });
+                            return Ok(());
+                        }
+                    }
+                    Err(Error::DeadTimeTooLong)
+                }
+
+                /// Enables the BKIN break input, forcing the outputs to their idle
+                /// state while active. `filter` is BKF, see the reference manual.
+                pub fn enable_break<PIN>(&mut self, pin: PIN, polarity: Polarity, filter: u8)
+                where
+                    PIN: BreakPin<$TIMX, Break>,
+                {
+                    assert!(filter < 16);
+                    pin.setup();
+                    self.tim.bdtr().modify(|_, w| unsafe {
+                        w.bkf()
+                            .bits(filter)
+                            .bkp()
+                            .bit(polarity == Polarity::ActiveHigh)
+                            .bke()
+                            .set_bit()
+                    });
+                }
+
+                pub fn disable_break(&mut self) {
+                    self.tim.bdtr().modify(|_, w| w.bke().clear_bit());
+                }
+
+                $(
+                    /// Enables the BKIN2 break input. `filter` is BK2F, see the
+                    /// reference manual.
+                    pub fn enable_break2<PIN>(&mut self, pin: PIN, polarity: Polarity, filter: u8)
+                    where
+                        PIN: BreakPin<$TIMX, $Break2>,
+                    {
+                        assert!(filter < 16);
+                        pin.setup();
+                        self.tim.bdtr().modify(|_, w| unsafe {
+                            w.bk2f()
+                                .bits(filter)
+                                .bk2p()
+                                .bit(polarity == Polarity::ActiveHigh)
+                                .bk2e()
+                                .set_bit()
+                        });
+                    }
+
+                    pub fn disable_break2(&mut self) {
+                        self.tim.bdtr().modify(|_, w| w.bk2e().clear_bit());
+                    }
+                )*
+
+                /// Returns true if a break event was detected
+                pub fn is_break(&self) -> bool {
+                    self.tim.sr().read().bif().bit_is_set()
+                }
+
+                /// Starts listening for break events
+                pub fn listen_break(&mut self) {
+                    self.tim.dier().modify(|_, w| w.bie().set_bit());
+                }
+
+                /// Stops listening for break events
+                pub fn unlisten_break(&mut self) {
+                    self.tim.dier().modify(|_, w| w.bie().clear_bit());
+                }
+
+                /// Clears break interrupt flag
+                pub fn clear_break_irq(&mut self) {
+                    self.tim.sr().modify(|_, w| w.bif().clear_bit());
+                }
+
+                /// Enables the outputs again after a break (MOE)
+                pub fn enable_outputs(&mut self) {
+                    self.tim.bdtr().modify(|_, w| w.moe().set_bit());
+                }
+
+                /// Forces the outputs to their idle state (MOE)
+                pub fn disable_outputs(&mut self) {
+                    self.tim.bdtr().modify(|_, w| w.moe().clear_bit());
+                }
+
+                /// Re-enables the outputs automatically at the next update event once
+                /// the break input is inactive (AOE)
+                pub fn set_automatic_output(&mut self, enable: bool) {
+                    self.tim.bdtr().modify(|_, w| w.aoe().bit(enable));
+                }
+
+                /// Write protects the configuration, call it after the dead-time and
+                /// break inputs are configured
+                pub fn set_lock_level(&mut self, level: LockLevel) {
+                    self.tim.bdtr().modify(|_, w| unsafe { w.lock().bits(level as u8) });
+                }
+            }
+        )+
+    };
+}
+
+pwm_complementary_hal! {
+    TIM1: (Channel1, cc1ne, cc1np),
+    TIM1: (Channel2, cc2ne, cc2np),
+    TIM1: (Channel3, cc3ne, cc3np),
+    TIM16: (Channel1, cc1ne, cc1np),
+    TIM17: (Channel1, cc1ne, cc1np),
+}
+
+#[cfg(any(feature = "stm32g070", feature = "stm32g071", feature = "stm32g081"))]
+pwm_complementary_hal! {
+    TIM15: (Channel1, cc1ne, cc1np),
+}
+
+pwm_break! {
+    TIM1: (Break2),
+    TIM16: (),
+    TIM17: (),
+}
+
+#[cfg(any(feature = "stm32g070", feature = "stm32g071", feature = "stm32g081"))]
+pwm_break! {
+    TIM15: (),
+}
+
 pwm_advanced_hal! {
-    TIM1:  (Channel1, cc1e: cc1ne, ccmr1_output, oc1pe, oc1m, ccr, moe),
-    TIM1:  (Channel2, cc2e: cc2ne, ccmr1_output, oc2pe, oc2m, ccr, moe),
-    TIM1:  (Channel3, cc3e: cc3ne, ccmr2_output, oc3pe, oc3m, ccr, moe),
+    TIM1:  (Channel1, cc1e, ccmr1_output, oc1pe, oc1m, ccr, moe),
+    TIM1:  (Channel2, cc2e, ccmr1_output, oc2pe, oc2m, ccr, moe),
+    TIM1:  (Channel3, cc3e, ccmr2_output, oc3pe, oc3m, ccr, moe),
     TIM1:  (Channel4, cc4e, ccmr2_output, oc4pe, oc4m, ccr, moe),
     TIM14: (Channel1, cc1e, ccmr1_output, oc1pe, oc1m, ccr),
-    TIM16: (Channel1, cc1e: cc1ne, ccmr1_output, oc1pe, oc1m, ccr, moe),
-    TIM17: (Channel1, cc1e: cc1ne, ccmr1_output, oc1pe, oc1m, ccr, moe),
+    TIM16: (Channel1, cc1e, ccmr1_output, oc1pe, oc1m, ccr, moe),
+    TIM17: (Channel1, cc1e, ccmr1_output, oc1pe, oc1m, ccr, moe),
 }
 
 #[cfg(any(feature = "stm32g070", feature = "stm32g071", feature = "stm32g081"))]
 pwm_advanced_hal! {
-    TIM15: (Channel1, cc1e: cc1ne, ccmr1_output, oc1pe, oc1m, ccr, moe),
+    TIM15: (Channel1, cc1e, ccmr1_output, oc1pe, oc1m, ccr, moe),
 }
 
 #[cfg(feature = "stm32g0x1")]
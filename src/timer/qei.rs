@@ -1,5 +1,12 @@
 //! Quadrature Encoder Interface
+//!
+//! The hardware counter is extended to an `i64` position by counting its
+//! overflows and underflows: call `update` from the timer interrupt after `listen`,
+//! or often enough that the counter cannot wrap twice in between. An index pulse on
+//! channel 3 is captured and resets the position to zero on the next `update`. The
+//! index cannot be taken from ETR, the slave mode controller is busy with encoding.
 use crate::rcc::*;
+use crate::time::MicroSecond;
 
 #[cfg(feature = "stm32g0x1")]
 use crate::stm32::{TIM1, TIM2, TIM3};
@@ -17,9 +24,26 @@ pub enum Direction {
     Downcounting,
 }
 
+/// Counted edges per encoder period
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum EncoderMode {
+    /// One count per period. The counter runs in x2 mode, the position is halved.
+    X1,
+    /// Counts both edges of TI1
+    X2,
+    /// Counts both edges of TI1 and TI2
+    X4,
+}
+
 pub struct Qei<TIM, PINS> {
     tim: TIM,
     pins: PINS,
+    mode: EncoderMode,
+    /// Position of counter value 0, in counter units
+    base: i64,
+    /// Position at the last velocity estimation
+    last: i64,
 }
 
 pub trait QeiPins<TIM> {
@@ -50,7 +74,7 @@ where
 }
 
 macro_rules! qei {
-    ($($TIMX:ident: ($tim:ident, $arr:ident, $cnt:ident),)+) => {
+    ($($TIMX:ident: ($tim:ident, $width:ident, $sms:ident, $ccr3:ident $(($i3:literal))*),)+) => {
         $(
             impl<PINS> Qei<$TIMX, PINS> where PINS: QeiPins<$TIMX> {
                 fn $tim(tim: $TIMX, pins: PINS, rcc: &mut Rcc) -> Self {
@@ -64,10 +88,7 @@ macro_rules! qei {
                     });
 
                     // Encoder mode 2.
-                    #[cfg(not(any(feature = "stm32g0b1", feature = "stm32g0c1")))]
-                    tim.smcr().write(|w| unsafe { w.sms().bits(0b010) });
-                    #[cfg(any(feature = "stm32g0b1", feature = "stm32g0c1"))]
-                    tim.smcr().write(|w| unsafe { w.sms1().bits(0b010) });
+                    tim.smcr().write(|w| unsafe { w.$sms().bits(0b010) });
 
                     // Enable and configure to capture on rising edge
                     tim.ccer().write(|w| {
@@ -87,18 +108,134 @@ macro_rules! qei {
 
                     pins.setup();
 
-                    tim.cr1().write(|w| w.cen().set_bit());
-                    Qei { tim, pins }
+                    tim.arr().write(|w| unsafe { w.bits($width::MAX as u32) });
+                    // Only overflows and underflows set the update flag
+                    tim.cr1().write(|w| w.urs().set_bit().cen().set_bit());
+                    Qei {
+                        tim,
+                        pins,
+                        mode: EncoderMode::X2,
+                        base: 0,
+                        last: 0,
+                    }
                 }
 
                 pub fn release(self) -> ($TIMX, PINS) {
                     (self.tim, self.pins.release())
                 }
 
-                pub fn count(&self) -> u16 {
-                    // TODO: this impl should change to u32 for counters that
-                    // have > 16 bits of resolution.
-                    self.tim.cnt().read().$cnt().bits() as u16
+                /// Selects the counted edges, the position is reset
+                pub fn set_mode(&mut self, mode: EncoderMode) {
+                    let sms = match mode {
+                        EncoderMode::X1 | EncoderMode::X2 => 0b010,
+                        EncoderMode::X4 => 0b011,
+                    };
+                    self.tim.smcr().modify(|_, w| unsafe { w.$sms().bits(sms) });
+                    self.mode = mode;
+                    self.reset_position();
+                }
+
+                /// Sets the input filter of both encoder inputs, see ICxF in the
+                /// reference manual
+                pub fn set_filter(&mut self, filter: u8) {
+                    assert!(filter < 16);
+                    self.tim
+                        .ccmr1_input()
+                        .modify(|_, w| unsafe { w.ic1f().bits(filter).ic2f().bits(filter) });
+                }
+
+                /// Captures the rising edge of the index pulse on channel 3
+                pub fn enable_index<PIN>(&mut self, pin: PIN)
+                where
+                    PIN: TimerPin<$TIMX, Channel = Channel3>,
+                {
+                    pin.setup();
+                    self.tim.ccmr2_input().modify(|_, w| unsafe { w.cc3s().bits(0b01) });
+                    self.tim.ccer().modify(|_, w| {
+                        w.cc3p().clear_bit().cc3np().clear_bit().cc3e().set_bit()
+                    });
+                }
+
+                /// Stops capturing the index pulse
+                pub fn disable_index(&mut self) {
+                    self.tim.ccer().modify(|_, w| w.cc3e().clear_bit());
+                    self.tim.dier().modify(|_, w| w.cc3ie().clear_bit());
+                }
+
+                /// Starts listening for counter wraps, and index pulses if enabled
+                pub fn listen(&mut self) {
+                    let index = self.tim.ccer().read().cc3e().bit_is_set();
+                    self.tim.dier().modify(|_, w| w.uie().set_bit().cc3ie().bit(index));
+                }
+
+                /// Stops listening
+                pub fn unlisten(&mut self) {
+                    self.tim.dier().modify(|_, w| w.uie().clear_bit().cc3ie().clear_bit());
+                }
+
+                /// Tracks counter wraps and index pulses, call from the timer interrupt
+                pub fn update(&mut self) {
+                    const PERIOD: i64 = $width::MAX as i64 + 1;
+                    const HALF: $width = $width::MAX >> 1;
+                    let sr = self.tim.sr().read();
+                    let count = self.count();
+                    let mut wrap = 0;
+                    if sr.uif().bit_is_set() {
+                        self.tim.sr().modify(|_, w| w.uif().clear_bit());
+                        // Just wrapped: close to 0 after an overflow, close to the
+                        // top after an underflow
+                        wrap = if count < HALF { PERIOD } else { -PERIOD };
+                        self.base += wrap;
+                    }
+                    if sr.cc3if().bit_is_set() {
+                        // Reading the capture clears the flag
+                        let index = self.tim.$ccr3($($i3)*).read().bits() as $width;
+                        // An index captured before the wrap is on the other side of it
+                        let before_wrap = wrap != 0 && (index < HALF) != (count < HALF);
+                        self.base = -(index as i64) + if before_wrap { wrap } else { 0 };
+                    }
+                }
+
+                /// Returns the extended position in encoder mode units
+                pub fn position(&mut self) -> i64 {
+                    loop {
+                        self.update();
+                        let count = self.count();
+                        if self.tim.sr().read().uif().bit_is_clear() {
+                            let position = self.base + count as i64;
+                            return match self.mode {
+                                EncoderMode::X1 => position.div_euclid(2),
+                                _ => position,
+                            };
+                        }
+                    }
+                }
+
+                /// Sets the current position to zero
+                pub fn reset_position(&mut self) {
+                    self.tim.cnt().reset();
+                    self.tim.sr().modify(|_, w| w.uif().clear_bit());
+                    self.base = 0;
+                    self.last = 0;
+                }
+
+                /// Estimates the velocity in counts per second from the position
+                /// change since the previous call, `elapsed` time ago. Saturates at
+                /// the `i32` range.
+                pub fn velocity(&mut self, elapsed: MicroSecond) -> i32 {
+                    let position = self.position();
+                    let delta = position - self.last;
+                    self.last = position;
+                    if elapsed.ticks() == 0 {
+                        return 0;
+                    }
+                    let velocity = delta.saturating_mul(1_000_000) / elapsed.ticks() as i64;
+                    velocity.clamp(i32::MIN as i64, i32::MAX as i64) as i32
+                }
+
+                /// Returns the raw counter value
+                pub fn count(&self) -> $width {
+                    self.tim.cnt().read().bits() as $width
                 }
 
                 pub fn direction(&self) -> Direction {
@@ -119,12 +256,21 @@ macro_rules! qei {
     }
 }
 
+#[cfg(not(any(feature = "stm32g0b1", feature = "stm32g0c1")))]
 qei! {
-    TIM1: (tim1, arr, cnt),
-    TIM3: (tim3, arr_l, cnt),
+    TIM1: (tim1, u16, sms, ccr(2)),
+    TIM3: (tim3, u16, sms, ccr3),
 }
 
+#[cfg(not(any(feature = "stm32g0b1", feature = "stm32g0c1")))]
 #[cfg(feature = "stm32g0x1")]
 qei! {
-    TIM2: (tim2, arr_l, cnt),  // TODO: missing high value register?
+    TIM2: (tim2, u32, sms, ccr3),
+}
+
+#[cfg(any(feature = "stm32g0b1", feature = "stm32g0c1"))]
+qei! {
+    TIM1: (tim1, u16, sms1, ccr(2)),
+    TIM2: (tim2, u32, sms1, ccr3),
+    TIM3: (tim3, u16, sms1, ccr3),
 }
@@ -0,0 +1,184 @@
+//! # Timer synchronization
+//!
+//! A master timer drives the trigger input of slave timers with its trigger output
+//! (TRGO), routed through the internal trigger lines (ITRx). Only the timer pairs
+//! wired together on the device implement [`InternalTrigger`], so invalid routes
+//! fail to compile. TIM14, TIM16 and TIM17 have no TRGO, their OC1 signal is routed.
+//!
+//! Trigger pins are selected with [`TriggerPin`](crate::timer::pins::TriggerPin),
+//! external clocks with [`Timer::use_external_clock`].
+use crate::stm32::*;
+use crate::timer::pwm::Pwm;
+use crate::timer::Timer;
+
+/// Trigger output (TRGO) source, see MMS in the reference manual
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum MasterMode {
+    /// Counter reset by UG or the slave mode controller
+    Reset = 0,
+    /// Counter enable
+    Enable = 1,
+    /// Update event
+    Update = 2,
+    /// Pulse on channel 1 capture or compare match
+    ComparePulse = 3,
+    /// OC1REF
+    Compare1 = 4,
+    /// OC2REF
+    Compare2 = 5,
+    /// OC3REF
+    Compare3 = 6,
+    /// OC4REF
+    Compare4 = 7,
+}
+
+/// Slave mode controller, see SMS in the reference manual
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum SlaveMode {
+    /// Counter clocked by the internal clock
+    Disabled = 0,
+    /// Rising edge of the trigger resets the counter
+    Reset = 0b100,
+    /// Counter runs while the trigger is high
+    Gated = 0b101,
+    /// Rising edge of the trigger starts the counter
+    Trigger = 0b110,
+    /// Rising edges of the trigger clock the counter
+    ExternalClock = 0b111,
+}
+
+/// Implemented by timers whose trigger output is wired to an internal trigger
+/// input of `SLAVE`
+pub trait InternalTrigger<SLAVE> {
+    #[doc(hidden)]
+    const ITR: u8;
+}
+
+macro_rules! internal_triggers {
+    ($SLAVE:ident: [$(($MASTER:ident, $itr:literal),)+]) => {
+        $(
+            impl InternalTrigger<$SLAVE> for $MASTER {
+                const ITR: u8 = $itr;
+            }
+        )+
+    };
+}
+
+macro_rules! master {
+    ($($T:ident: [$($TIM:ident),+],)+) => {
+        $(
+            $(
+                impl $T<$TIM> {
+                    /// Selects the trigger output (TRGO) source
+                    pub fn set_master_mode(&mut self, mode: MasterMode) {
+                        let tim = unsafe { &*$TIM::ptr() };
+                        tim.cr2().modify(|_, w| unsafe { w.mms().bits(mode as u8) });
+                    }
+                }
+            )+
+        )+
+    };
+}
+
+macro_rules! slave {
+    ($($T:ident: [$($TIM:ident: ($sms:ident, $ts:ident)),+],)+) => {
+        $(
+            $(
+                impl $T<$TIM> {
+                    /// Configures the slave mode controller. The trigger is selected
+                    /// with `set_internal_trigger` or a `TriggerPin`. In trigger mode
+                    /// the counter is stopped until the trigger starts it.
+                    pub fn set_slave_mode(&mut self, mode: SlaveMode) {
+                        let tim = unsafe { &*$TIM::ptr() };
+                        if mode == SlaveMode::Trigger {
+                            tim.cr1().modify(|_, w| w.cen().clear_bit());
+                            tim.cnt().reset();
+                        }
+                        tim.smcr().modify(|_, w| unsafe { w.$sms().bits(mode as u8) });
+                    }
+
+                    /// Selects the trigger output of `MASTER` as trigger input
+                    pub fn set_internal_trigger<MASTER>(&mut self)
+                    where
+                        MASTER: InternalTrigger<$TIM>,
+                    {
+                        let tim = unsafe { &*$TIM::ptr() };
+                        tim.smcr().modify(|_, w| unsafe { w.$ts().bits(MASTER::ITR) });
+                    }
+
+                    /// Delays the trigger output to synchronize the slaves of this
+                    /// timer with its own trigger input (MSM)
+                    pub fn set_master_slave_sync(&mut self, enable: bool) {
+                        let tim = unsafe { &*$TIM::ptr() };
+                        tim.smcr().modify(|_, w| w.msm().bit(enable));
+                    }
+                }
+            )+
+        )+
+    };
+}
+
+master! {
+    Timer: [TIM1, TIM3],
+    Pwm: [TIM1, TIM3],
+}
+
+#[cfg(feature = "stm32g0x1")]
+master! {
+    Timer: [TIM2],
+    Pwm: [TIM2],
+}
+
+#[cfg(any(feature = "stm32g070", feature = "stm32g071", feature = "stm32g081"))]
+master! {
+    Timer: [TIM6, TIM7, TIM15],
+    Pwm: [TIM15],
+}
+
+#[cfg(not(any(feature = "stm32g0b1", feature = "stm32g0c1")))]
+slave! {
+    Timer: [TIM1: (sms, ts), TIM3: (sms, ts)],
+    Pwm: [TIM1: (sms, ts), TIM3: (sms, ts)],
+}
+
+#[cfg(not(any(feature = "stm32g0b1", feature = "stm32g0c1")))]
+#[cfg(feature = "stm32g0x1")]
+slave! {
+    Timer: [TIM2: (sms, ts)],
+    Pwm: [TIM2: (sms, ts)],
+}
+
+#[cfg(any(feature = "stm32g0b1", feature = "stm32g0c1"))]
+slave! {
+    Timer: [TIM1: (sms1, ts1), TIM2: (sms1, ts1), TIM3: (sms1, ts1)],
+    Pwm: [TIM1: (sms1, ts1), TIM2: (sms1, ts1), TIM3: (sms1, ts1)],
+}
+
+#[cfg(any(feature = "stm32g070", feature = "stm32g071", feature = "stm32g081"))]
+slave! {
+    Timer: [TIM15: (sms1, ts)],
+    Pwm: [TIM15: (sms1, ts)],
+}
+
+internal_triggers!(TIM1: [(TIM3, 2), (TIM17, 3),]);
+internal_triggers!(TIM3: [(TIM1, 0), (TIM14, 3),]);
+
+#[cfg(feature = "stm32g0x1")]
+internal_triggers!(TIM1: [(TIM2, 1),]);
+#[cfg(feature = "stm32g0x1")]
+internal_triggers!(TIM2: [(TIM1, 0), (TIM3, 2), (TIM14, 3),]);
+#[cfg(feature = "stm32g0x1")]
+internal_triggers!(TIM3: [(TIM2, 1),]);
+
+#[cfg(any(feature = "stm32g070", feature = "stm32g071", feature = "stm32g081"))]
+internal_triggers!(TIM1: [(TIM15, 0),]);
+#[cfg(any(feature = "stm32g070", feature = "stm32g071", feature = "stm32g081"))]
+internal_triggers!(TIM3: [(TIM15, 2),]);
+#[cfg(any(feature = "stm32g070", feature = "stm32g071", feature = "stm32g081"))]
+internal_triggers!(TIM15: [(TIM3, 1), (TIM16, 2), (TIM17, 3),]);
+#[cfg(any(feature = "stm32g071", feature = "stm32g081"))]
+internal_triggers!(TIM2: [(TIM15, 1),]);
+#[cfg(any(feature = "stm32g071", feature = "stm32g081"))]
+internal_triggers!(TIM15: [(TIM2, 0),]);
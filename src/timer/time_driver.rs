@@ -0,0 +1,245 @@
+//! # Embassy time driver
+//!
+//! [`embassy_time_driver::Driver`] on TIM2, TIM3 or TIM15, selected with one of the
+//! `time-driver-tim2`, `time-driver-tim3` and `time-driver-tim15` features. The
+//! timer counts at the embassy tick rate, the 16-bit counter is extended with a
+//! half-period compare on channel 2 and the overflow interrupt, channel 1 raises
+//! the alarm of the timer queue. Call [`init`] once before using `embassy_time`.
+//!
+//! The feature also defines the timer interrupt handler.
+use core::cell::{Cell, RefCell};
+use core::sync::atomic::{compiler_fence, AtomicU32, Ordering};
+use core::task::Waker;
+
+use cortex_m::interrupt::{self, CriticalSection, Mutex};
+use cortex_m::peripheral::NVIC;
+use embassy_time_driver::{Driver, TICK_HZ};
+use embassy_time_queue_utils::Queue;
+
+use crate::rcc::{Enable, Rcc, Reset};
+use crate::stm32::{self, Interrupt};
+
+#[cfg(any(
+    all(feature = "time-driver-tim2", feature = "time-driver-tim3"),
+    all(feature = "time-driver-tim2", feature = "time-driver-tim15"),
+    all(feature = "time-driver-tim3", feature = "time-driver-tim15"),
+))]
+compile_error!("only one time-driver-timX feature can be enabled");
+
+#[cfg(feature = "time-driver-tim2")]
+use stm32::TIM2 as TIM;
+#[cfg(feature = "time-driver-tim2")]
+const IRQ: Interrupt = Interrupt::TIM2;
+
+#[cfg(feature = "time-driver-tim3")]
+use stm32::TIM3 as TIM;
+#[cfg(all(
+    feature = "time-driver-tim3",
+    not(any(feature = "stm32g0b1", feature = "stm32g0c1"))
+))]
+const IRQ: Interrupt = Interrupt::TIM3;
+#[cfg(all(
+    feature = "time-driver-tim3",
+    any(feature = "stm32g0b1", feature = "stm32g0c1")
+))]
+const IRQ: Interrupt = Interrupt::TIM3_TIM4;
+
+#[cfg(feature = "time-driver-tim15")]
+use stm32::TIM15 as TIM;
+#[cfg(feature = "time-driver-tim15")]
+const IRQ: Interrupt = Interrupt::TIM15;
+
+/// Half of the 16-bit counter range
+const HALF: u16 = 0x8000;
+
+/// Returns the 64-bit time from the half-period count and the counter value
+fn calc_now(period: u32, counter: u16) -> u64 {
+    ((period as u64) << 15) + ((counter as u32 ^ ((period & 1) << 15)) as u64)
+}
+
+struct TimDriver {
+    /// Number of half periods elapsed, incremented on overflow and half-period
+    period: AtomicU32,
+    /// Timestamp of the alarm, `u64::MAX` when none is set
+    alarm: Mutex<Cell<u64>>,
+    queue: Mutex<RefCell<Queue>>,
+}
+
+embassy_time_driver::time_driver_impl!(static DRIVER: TimDriver = TimDriver {
+    period: AtomicU32::new(0),
+    alarm: Mutex::new(Cell::new(u64::MAX)),
+    queue: Mutex::new(RefCell::new(Queue::new())),
+});
+
+fn tim() -> &'static <TIM as core::ops::Deref>::Target {
+    unsafe { &*TIM::ptr() }
+}
+
+#[cfg(not(feature = "time-driver-tim15"))]
+fn set_compare(channel: usize, value: u16) {
+    match channel {
+        0 => tim().ccr1().write(|w| unsafe { w.bits(value as u32) }),
+        _ => tim().ccr2().write(|w| unsafe { w.bits(value as u32) }),
+    };
+}
+
+#[cfg(feature = "time-driver-tim15")]
+fn set_compare(channel: usize, value: u16) {
+    tim()
+        .ccr(channel)
+        .write(|w| unsafe { w.bits(value as u32) });
+}
+
+/// Starts the time driver, the timer clock must be a multiple of the embassy tick rate
+pub fn init(_tim: TIM, rcc: &mut Rcc) {
+    let clk = rcc.clocks.apb_tim_clk.raw() as u64;
+    assert!(clk % TICK_HZ == 0, "tick rate must divide the timer clock");
+    let psc = clk / TICK_HZ - 1;
+    assert!(psc <= 0xffff);
+
+    TIM::enable(rcc);
+    TIM::reset(rcc);
+
+    let tim = tim();
+    tim.psc().write(|w| unsafe { w.bits(psc as u32) });
+    tim.arr().write(|w| unsafe { w.bits(u16::MAX as u32) });
+    // Load the prescaler without an update interrupt
+    tim.cr1().modify(|_, w| w.urs().set_bit());
+    tim.egr().write(|w| w.ug().set_bit());
+    tim.sr().reset();
+
+    // Half-period compare extends the counter together with overflows
+    set_compare(1, HALF);
+    tim.dier().write(|w| w.uie().set_bit().cc2ie().set_bit());
+
+    tim.cr1().modify(|_, w| w.cen().set_bit());
+
+    unsafe {
+        NVIC::unmask(IRQ);
+    }
+}
+
+impl TimDriver {
+    fn on_interrupt(&self) {
+        interrupt::free(|cs| {
+            let tim = tim();
+            let sr = tim.sr().read();
+            let dier = tim.dier().read();
+            // Clear only the flags read, other ones may have been set meanwhile
+            tim.sr().write(|w| unsafe { w.bits(!sr.bits()) });
+
+            if sr.uif().bit_is_set() {
+                self.next_period(cs);
+            }
+            if sr.cc2if().bit_is_set() {
+                self.next_period(cs);
+            }
+            if sr.cc1if().bit_is_set() && dier.cc1ie().bit_is_set() {
+                self.trigger_alarm(cs);
+            }
+        });
+    }
+
+    fn next_period(&self, cs: &CriticalSection) {
+        // Only written from the interrupt, a load and store is enough
+        let period = self.period.load(Ordering::Relaxed) + 1;
+        self.period.store(period, Ordering::Relaxed);
+        let t = (period as u64) << 15;
+
+        // Enable the compare once the alarm is less than a period away
+        let at = self.alarm.borrow(cs).get();
+        if at < t + 0xc000 {
+            tim().dier().modify(|_, w| w.cc1ie().set_bit());
+        }
+    }
+
+    fn trigger_alarm(&self, cs: &CriticalSection) {
+        let mut queue = self.queue.borrow(cs).borrow_mut();
+        let mut next = queue.next_expiration(self.now());
+        while !self.set_alarm(cs, next) {
+            next = queue.next_expiration(self.now());
+        }
+    }
+
+    /// Returns false if the timestamp is already in the past
+    fn set_alarm(&self, cs: &CriticalSection, timestamp: u64) -> bool {
+        let tim = tim();
+        self.alarm.borrow(cs).set(timestamp);
+
+        let t = self.now();
+        if timestamp <= t {
+            tim.dier().modify(|_, w| w.cc1ie().clear_bit());
+            self.alarm.borrow(cs).set(u64::MAX);
+            return false;
+        }
+
+        set_compare(0, timestamp as u16);
+        // Far alarms are enabled by `next_period`
+        let diff = timestamp - t;
+        tim.dier().modify(|_, w| w.cc1ie().bit(diff < 0xc000));
+
+        // The counter may have passed the timestamp while the compare was set
+        let t = self.now();
+        if timestamp <= t {
+            tim.dier().modify(|_, w| w.cc1ie().clear_bit());
+            self.alarm.borrow(cs).set(u64::MAX);
+            return false;
+        }
+        true
+    }
+}
+
+impl Driver for TimDriver {
+    fn now(&self) -> u64 {
+        let period = self.period.load(Ordering::Relaxed);
+        compiler_fence(Ordering::Acquire);
+        let counter = tim().cnt().read().bits() as u16;
+        calc_now(period, counter)
+    }
+
+    fn schedule_wake(&self, at: u64, waker: &Waker) {
+        interrupt::free(|cs| {
+            let mut queue = self.queue.borrow(cs).borrow_mut();
+            if queue.schedule_wake(at, waker) {
+                let mut next = queue.next_expiration(self.now());
+                while !self.set_alarm(cs, next) {
+                    next = queue.next_expiration(self.now());
+                }
+            }
+        })
+    }
+}
+
+#[cfg(feature = "time-driver-tim2")]
+#[no_mangle]
+#[allow(non_snake_case)]
+unsafe extern "C" fn TIM2() {
+    DRIVER.on_interrupt();
+}
+
+#[cfg(all(
+    feature = "time-driver-tim3",
+    not(any(feature = "stm32g0b1", feature = "stm32g0c1"))
+))]
+#[no_mangle]
+#[allow(non_snake_case)]
+unsafe extern "C" fn TIM3() {
+    DRIVER.on_interrupt();
+}
+
+#[cfg(all(
+    feature = "time-driver-tim3",
+    any(feature = "stm32g0b1", feature = "stm32g0c1")
+))]
+#[no_mangle]
+#[allow(non_snake_case)]
+unsafe extern "C" fn TIM3_TIM4() {
+    DRIVER.on_interrupt();
+}
+
+#[cfg(feature = "time-driver-tim15")]
+#[no_mangle]
+#[allow(non_snake_case)]
+unsafe extern "C" fn TIM15() {
+    DRIVER.on_interrupt();
+}
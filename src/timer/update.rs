@@ -0,0 +1,112 @@
+//! # Update event control
+//!
+//! The update event (UEV) reloads the preloaded registers (PSC, ARR when
+//! preloaded, CCRx when preloaded) and sets the update flag. Advanced and general
+//! purpose timers with a repetition counter (TIM1, TIM15, TIM16 and TIM17) only
+//! generate it every N counter periods. In center-aligned mode a counter period
+//! is half of the PWM period.
+use crate::stm32::*;
+use crate::timer::pwm::Pwm;
+use crate::timer::Timer;
+
+/// Events setting the update flag and requesting interrupt or DMA, see URS in the
+/// reference manual
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum UpdateSource {
+    /// Counter overflow or underflow, UG bit and slave mode controller resets
+    Any,
+    /// Counter overflow or underflow only
+    CounterOnly,
+}
+
+macro_rules! update_control {
+    ($($T:ident: [$($TIM:ident),+],)+) => {
+        $(
+            $(
+                impl $T<$TIM> {
+                    /// Selects the events setting the update flag
+                    pub fn set_update_source(&mut self, source: UpdateSource) {
+                        let tim = unsafe { &*$TIM::ptr() };
+                        tim.cr1()
+                            .modify(|_, w| w.urs().bit(source == UpdateSource::CounterOnly));
+                    }
+
+                    /// Enables or disables the update event (UDIS). While disabled the
+                    /// shadow registers keep their values and the counter still wraps.
+                    pub fn set_update_enabled(&mut self, enable: bool) {
+                        let tim = unsafe { &*$TIM::ptr() };
+                        tim.cr1().modify(|_, w| w.udis().bit(!enable));
+                    }
+
+                    /// Buffers the auto-reload register (ARPE), a new period then only
+                    /// takes effect on the next update event
+                    pub fn set_auto_reload_preload(&mut self, enable: bool) {
+                        let tim = unsafe { &*$TIM::ptr() };
+                        tim.cr1().modify(|_, w| w.arpe().bit(enable));
+                    }
+
+                    /// Forces an update event: reinitializes the counter and loads the
+                    /// preloaded registers. The update flag is only set if the update
+                    /// source is `UpdateSource::Any`.
+                    pub fn generate_update(&mut self) {
+                        let tim = unsafe { &*$TIM::ptr() };
+                        tim.egr().write(|w| w.ug().set_bit());
+                    }
+                }
+            )+
+        )+
+    };
+}
+
+macro_rules! repetition {
+    ($($T:ident: [$($TIM:ident: $rep:ty),+],)+) => {
+        $(
+            $(
+                impl $T<$TIM> {
+                    /// Generates the update event every `periods` counter periods,
+                    /// applied on the next update event
+                    pub fn set_repetition(&mut self, periods: u32) {
+                        assert!(periods >= 1 && periods - 1 <= <$rep>::MAX as u32);
+                        let tim = unsafe { &*$TIM::ptr() };
+                        tim.rcr().write(|w| unsafe { w.rep().bits((periods - 1) as $rep) });
+                    }
+
+                    /// Returns the number of counter periods between update events
+                    pub fn repetition(&self) -> u32 {
+                        let tim = unsafe { &*$TIM::ptr() };
+                        tim.rcr().read().rep().bits() as u32 + 1
+                    }
+                }
+            )+
+        )+
+    };
+}
+
+update_control! {
+    Timer: [TIM1, TIM3, TIM14, TIM16, TIM17],
+    Pwm: [TIM1, TIM3, TIM14, TIM16, TIM17],
+}
+
+#[cfg(feature = "stm32g0x1")]
+update_control! {
+    Timer: [TIM2],
+    Pwm: [TIM2],
+}
+
+#[cfg(any(feature = "stm32g070", feature = "stm32g071", feature = "stm32g081"))]
+update_control! {
+    Timer: [TIM6, TIM7, TIM15],
+    Pwm: [TIM15],
+}
+
+repetition! {
+    Timer: [TIM1: u16, TIM16: u8, TIM17: u8],
+    Pwm: [TIM1: u16, TIM16: u8, TIM17: u8],
+}
+
+#[cfg(any(feature = "stm32g070", feature = "stm32g071", feature = "stm32g081"))]
+repetition! {
+    Timer: [TIM15: u8],
+    Pwm: [TIM15: u8],
+}